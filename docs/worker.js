@@ -24,15 +24,75 @@ function handle(msg) {
     case 'make_ai_move':
       result = game.make_ai_move();
       break;
+    case 'compute_ai_move':
+      result = game.compute_ai_move();
+      break;
     case 'get_hint':
       result = game.get_hint(...args);
       break;
+    case 'make_ai_move_restricted':
+      result = game.make_ai_move_restricted(...args);
+      break;
+    case 'explain_ai_move':
+      result = game.explain_ai_move();
+      break;
     case 'get_legal_moves_for_square':
       result = game.get_legal_moves_for_square(...args);
       break;
+    case 'get_forcing_moves':
+      result = game.get_forcing_moves();
+      break;
+    case 'get_display_eval':
+      result = game.get_display_eval();
+      break;
+    case 'get_hanging_pieces':
+      result = game.get_hanging_pieces(...args);
+      break;
+    case 'get_move_targets_heatmap':
+      result = game.get_move_targets_heatmap();
+      break;
     case 'get_eval_breakdown':
       result = game.get_eval_breakdown();
       break;
+    case 'view_ply':
+      result = game.view_ply(...args);
+      break;
+    case 'play_from_current':
+      result = game.play_from_current();
+      break;
+    case 'is_legal_uci':
+      result = game.is_legal_uci(args[0]);
+      break;
+    case 'get_fen':
+      result = game.get_fen();
+      break;
+    case 'get_fen_history':
+      result = game.get_fen_history();
+      break;
+    case 'get_legal_moves_san':
+      result = game.get_legal_moves_san();
+      break;
+    case 'get_material_signature':
+      result = game.get_material_signature();
+      break;
+    case 'load_pgn':
+      result = game.load_pgn(args[0]);
+      break;
+    case 'get_pgn':
+      result = game.get_pgn();
+      break;
+    case 'get_opening_name':
+      result = game.get_opening_name();
+      break;
+    case 'analyze_history':
+      result = game.analyze_history();
+      break;
+    case 'get_move_evaluations':
+      result = game.get_move_evaluations();
+      break;
+    case 'get_root_stats':
+      result = game.get_root_stats();
+      break;
     case 'get_last_evals':
       result = Number(game.get_last_evals());
       break;
@@ -40,6 +100,22 @@ function handle(msg) {
       game.set_module(...args);
       result = null;
       break;
+    case 'set_variant':
+      game.set_variant(...args);
+      result = null;
+      break;
+    case 'set_time_budget_ms':
+      game.set_time_budget_ms(args[0]);
+      result = null;
+      break;
+    case 'set_no_early_queen_until':
+      game.set_no_early_queen_until(args[0]);
+      result = null;
+      break;
+    case 'set_deterministic':
+      game.set_deterministic(...args);
+      result = null;
+      break;
     case 'set_depth':
       game.set_depth(...args);
       result = null;
@@ -48,6 +124,10 @@ function handle(msg) {
       game.set_auto_deepen(args[0], BigInt(args[1]));
       result = null;
       break;
+    case 'set_seed':
+      game.set_seed(args[0] == null ? undefined : BigInt(args[0]));
+      result = null;
+      break;
     default:
       postMessage({ id, error: 'unknown method: ' + method });
       return;
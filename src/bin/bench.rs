@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use chess::board::Board;
+use chess::engine::{pick_move, AiConfig};
+
+/// Positions searched by the benchmark, as UCI moves from the start.
+const POSITIONS: &[(&str, &[&str])] = &[
+    ("start", &[]),
+    ("italian", &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"]),
+    ("qga", &["d2d4", "d7d5", "c2c4", "d5c4", "e2e4"]),
+    ("sicilian", &["e2e4", "c7c5", "g1f3", "d7d6", "d2d4", "c5d4", "f3d4", "g8f6", "b1c3"]),
+];
+
+fn pct(part: Duration, total: Duration) -> f64 {
+    100.0 * part.as_secs_f64() / total.as_secs_f64().max(1e-9)
+}
+
+fn main() {
+    let mut config = AiConfig::new();
+    config.depth = 2;
+    config.auto_deepen = false;
+    config.deterministic = true;
+    config.profile = true;
+
+    println!("{:<10} {:>6} {:>10} {:>9} {:>9} {:>8} {:>8} {:>8}", "position", "move", "nodes", "time", "nodes/s", "movegen", "eval", "make");
+    for (name, moves) in POSITIONS {
+        let board = Board::from_uci_moves(moves).expect("benchmark line is legal");
+        let result = pick_move(&board, &config).expect("benchmark position has moves");
+        let p = result.profile.expect("profiling is on");
+        println!(
+            "{name:<10} {:>6} {:>10} {:>8.2}s {:>9.0} {:>7.1}% {:>7.1}% {:>7.1}%",
+            result.mv.to_uci(),
+            result.evals,
+            p.total.as_secs_f64(),
+            result.evals as f64 / p.total.as_secs_f64().max(1e-9),
+            pct(p.movegen, p.total),
+            pct(p.eval, p.total),
+            pct(p.make_move, p.total),
+        );
+    }
+}
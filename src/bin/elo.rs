@@ -3,7 +3,7 @@ use std::process::{Command, Stdio};
 use std::time::Instant;
 
 use chess::board::Board;
-use chess::engine::{pick_move, AiConfig};
+use chess::engine::{pick_move_or_reason, AiConfig};
 use chess::moves::Move;
 use chess::piece::Color;
 
@@ -11,6 +11,55 @@ const STOCKFISH_PATH: &str = "/home/patrick/.local/bin/stockfish";
 const MAX_MOVES: u32 = 200;
 const GAMES_PER_CONFIG: usize = 16;
 
+/// Short, roughly balanced book lines (UCI). Stockfish at fixed movetime is
+/// near-deterministic, so starting every game from the same position mostly
+/// replays the same game; spreading games over these keeps the sample
+/// independent.
+const BALANCED_OPENINGS: &[&[&str]] = &[
+    &["e2e4", "e7e5", "g1f3", "b8c6"], // Open game
+    &["e2e4", "c7c5", "g1f3", "d7d6"], // Sicilian
+    &["e2e4", "e7e6", "d2d4", "d7d5"], // French
+    &["e2e4", "c7c6", "d2d4", "d7d5"], // Caro-Kann
+    &["d2d4", "d7d5", "c2c4", "e7e6"], // Queen's Gambit Declined
+    &["d2d4", "g8f6", "c2c4", "g7g6"], // King's Indian
+    &["c2c4", "e7e5", "b1c3", "g8f6"], // English
+    &["g1f3", "d7d5", "g2g3", "g8f6"], // Réti
+];
+
+/// Which starting positions games are played from (`--openings <set>`).
+#[derive(Clone, Copy)]
+enum OpeningSet {
+    /// Every game from the initial position
+    Start,
+    /// Cycle through `BALANCED_OPENINGS`
+    Balanced,
+}
+
+impl OpeningSet {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "start" => Some(OpeningSet::Start),
+            "balanced" => Some(OpeningSet::Balanced),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            OpeningSet::Start => "start",
+            OpeningSet::Balanced => "balanced",
+        }
+    }
+
+    /// Opening line for the `i`th game of a colour.
+    fn line(self, i: usize) -> &'static [&'static str] {
+        match self {
+            OpeningSet::Start => &[],
+            OpeningSet::Balanced => BALANCED_OPENINGS[i % BALANCED_OPENINGS.len()],
+        }
+    }
+}
+
 struct StockfishEngine {
     child: std::process::Child,
     stdin: std::process::ChildStdin,
@@ -101,10 +150,11 @@ fn play_game(
     ai_color: Color,
     sf_skill: u32,
     sf_time_ms: u32,
+    opening: &[&str],
 ) -> GameResult {
     let mut sf = StockfishEngine::new(sf_skill, sf_time_ms);
-    let mut board = Board::new();
-    let mut uci_moves: Vec<String> = Vec::new();
+    let mut board = Board::from_uci_moves(opening).expect("opening line must be legal");
+    let mut uci_moves: Vec<String> = opening.iter().map(|m| m.to_string()).collect();
     let mut ai_moves = 0u32;
     let mut ai_time_secs = 0.0f64;
 
@@ -115,14 +165,14 @@ fn play_game(
 
         if board.current_turn == ai_color {
             let start = Instant::now();
-            match pick_move(&board, ai_config) {
-                Some(result) => {
+            match pick_move_or_reason(&board, ai_config) {
+                Ok(result) => {
                     ai_time_secs += start.elapsed().as_secs_f64();
                     ai_moves += 1;
                     uci_moves.push(result.mv.to_uci());
                     board.apply_move(&result.mv);
                 }
-                None => break,
+                Err(_) => break, // game decided; board.result says how
             }
         } else {
             let sf_uci = sf.get_best_move(&uci_moves, sf_time_ms);
@@ -187,10 +237,12 @@ fn run_config(
     config: &AiConfig,
     sf_skill: u32,
     sf_time_ms: u32,
+    openings: OpeningSet,
 ) -> (u32, u32, u32, f64) {
     let half = GAMES_PER_CONFIG / 2;
 
-    // Build a list of (game_index, ai_color) tasks
+    // Build a list of (game_index, ai_color) tasks. Game i uses the same
+    // opening with both colours, so each line is played from both sides.
     let mut tasks: Vec<(usize, Color)> = Vec::new();
     for i in 0..half {
         tasks.push((i, Color::White));
@@ -207,7 +259,7 @@ fn run_config(
             let label = label.to_string();
             std::thread::spawn(move || {
                 let side = if color == Color::White { "W" } else { "B" };
-                let gr = play_game(&config, color, sf_skill, sf_time_ms);
+                let gr = play_game(&config, color, sf_skill, sf_time_ms, openings.line(i));
                 println!("  [{label} as {side}] game {}: {}", i + 1, gr.outcome);
                 gr
             })
@@ -239,6 +291,24 @@ fn run_config(
 }
 
 fn main() {
+    let mut openings = OpeningSet::Balanced;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--openings" => {
+                let value = args.next().unwrap_or_default();
+                openings = OpeningSet::parse(&value).unwrap_or_else(|| {
+                    eprintln!("Unknown opening set '{value}' (expected: start, balanced)");
+                    std::process::exit(2);
+                });
+            }
+            _ => {
+                eprintln!("Usage: elo [--openings start|balanced]");
+                std::process::exit(2);
+            }
+        }
+    }
+
     println!("=== Chess Engine ELO Estimation vs Stockfish ===\n");
 
     // Stockfish at very low skill (Skill 0 ≈ ~800 ELO, Skill 1 ≈ ~1000)
@@ -248,6 +318,7 @@ fn main() {
 
     println!("Stockfish config: skill={sf_skill}, movetime={sf_time_ms}ms");
     println!("Games per config: {GAMES_PER_CONFIG} ({} as White, {} as Black)", GAMES_PER_CONFIG / 2, GAMES_PER_CONFIG / 2);
+    println!("Openings: {}", openings.name());
     println!();
 
     let configs: Vec<(&str, AiConfig)> = vec![
@@ -278,7 +349,7 @@ fn main() {
     println!("{}", "-".repeat(65));
 
     for (label, config) in &configs {
-        let (w, d, l, avg_ms) = run_config(label, config, sf_skill, sf_time_ms);
+        let (w, d, l, avg_ms) = run_config(label, config, sf_skill, sf_time_ms, openings);
         let elo = elo_diff(w, d, l);
         println!(
             "{:<25} {:>4} {:>5} {:>6} {:>+8.0} {:>10.1}",
@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use chess::board::Board;
+
+const DEFAULT_DEPTH: u32 = 5;
+
+fn usage() -> ! {
+    eprintln!("Usage: perft [--depth <plies>] [--threads <n>] [<uci moves>...]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let mut depth = DEFAULT_DEPTH;
+    let mut threads = 1;
+    let mut moves: Vec<String> = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = args.next().and_then(|v| v.parse().ok()).unwrap_or_else(|| usage()),
+            "--threads" => {
+                threads = args.next().and_then(|v| v.parse().ok()).filter(|&n| n > 0).unwrap_or_else(|| usage())
+            }
+            _ if arg.starts_with("--") => usage(),
+            _ => moves.push(arg),
+        }
+    }
+
+    let moves: Vec<&str> = moves.iter().map(String::as_str).collect();
+    let board = Board::from_uci_moves(&moves).unwrap_or_else(|| {
+        eprintln!("Illegal move sequence: {}", moves.join(" "));
+        std::process::exit(2);
+    });
+
+    for d in 1..=depth {
+        let start = Instant::now();
+        let nodes = board.perft_parallel(d, threads);
+        let secs = start.elapsed().as_secs_f64();
+        println!("perft({d}) = {nodes} [{secs:.2}s, {:.0} nodes/s]", nodes as f64 / secs.max(1e-9));
+    }
+}
@@ -1,10 +1,13 @@
 use std::io::Write;
 use chess::board::Board;
-use chess::engine::{pick_move, AiConfig, Weights};
+use chess::engine::{evaluate_pov, pick_move_or_reason, AiConfig, Pov, Weights};
+use chess::piece::Color;
 
 const MAX_MOVES: u32 = 150;
 const GAMES_PER_MATCHUP: usize = 10;
 const PHASE2_GAMES: usize = 10;
+/// Default `--adjudicate-threshold`, in pawns.
+const DEFAULT_ADJUDICATION_THRESHOLD: f64 = 3.0;
 
 #[derive(Debug)]
 struct MatchResult {
@@ -13,20 +16,24 @@ struct MatchResult {
     draws: u32,
 }
 
-fn play_game(white_config: &AiConfig, black_config: &AiConfig) -> Option<&'static str> {
+/// Games that hit `MAX_MOVES` are draws unless `adjudicate` is set: then the
+/// final position is scored by `evaluate` with default weights (so the judge
+/// is neutral between the configs under test), and a lead beyond the
+/// threshold counts as a win for that side.
+fn play_game(white_config: &AiConfig, black_config: &AiConfig, adjudicate: Option<f64>) -> Option<&'static str> {
     let mut board = Board::new();
     for _ in 0..MAX_MOVES {
         if board.game_over {
             break;
         }
-        let config = if board.current_turn == chess::piece::Color::White {
+        let config = if board.current_turn == Color::White {
             white_config
         } else {
             black_config
         };
-        match pick_move(&board, config) {
-            Some(result) => board.apply_move(&result.mv),
-            None => break,
+        match pick_move_or_reason(&board, config) {
+            Ok(result) => board.apply_move(&result.mv),
+            Err(_) => break, // game decided; board.result says how
         }
     }
 
@@ -37,7 +44,20 @@ fn play_game(white_config: &AiConfig, black_config: &AiConfig) -> Option<&'stati
             _ => "draw",
         })
     } else {
-        Some("draw") // hit move limit
+        // Hit move limit
+        match adjudicate {
+            Some(threshold) => {
+                let score = evaluate_pov(&board, Pov::White, &AiConfig::new());
+                if score > threshold {
+                    Some("white")
+                } else if score < -threshold {
+                    Some("black")
+                } else {
+                    Some("draw")
+                }
+            }
+            None => Some("draw"),
+        }
     }
 }
 
@@ -46,8 +66,9 @@ fn run_matchup(
     config_a: &AiConfig,
     label_b: &str,
     config_b: &AiConfig,
+    adjudicate: Option<f64>,
 ) -> MatchResult {
-    run_matchup_n(label_a, config_a, label_b, config_b, GAMES_PER_MATCHUP)
+    run_matchup_n(label_a, config_a, label_b, config_b, GAMES_PER_MATCHUP, adjudicate)
 }
 
 fn run_matchup_n(
@@ -56,6 +77,7 @@ fn run_matchup_n(
     label_b: &str,
     config_b: &AiConfig,
     num_games: usize,
+    adjudicate: Option<f64>,
 ) -> MatchResult {
     let mut result = MatchResult {
         white_wins: 0,
@@ -67,7 +89,7 @@ fn run_matchup_n(
 
     // A as white, B as black
     for _ in 0..half {
-        match play_game(config_a, config_b) {
+        match play_game(config_a, config_b, adjudicate) {
             Some("white") => result.white_wins += 1,
             Some("black") => result.black_wins += 1,
             _ => result.draws += 1,
@@ -84,7 +106,7 @@ fn run_matchup_n(
         draws: 0,
     };
     for _ in 0..half {
-        match play_game(config_b, config_a) {
+        match play_game(config_b, config_a, adjudicate) {
             Some("white") => result2.white_wins += 1,
             Some("black") => result2.black_wins += 1,
             _ => result2.draws += 1,
@@ -117,6 +139,32 @@ fn medium_config() -> AiConfig {
     c
 }
 
+/// How much one weight matters: the spread of net wins vs baseline across its
+/// variations. Only the variations count, so a weight whose every variation
+/// loses has a negative `max_net`.
+#[derive(serde::Serialize)]
+struct Sensitivity<'a> {
+    weight: &'a str,
+    min_net: i32,
+    max_net: i32,
+    spread: i32,
+}
+
+fn sensitivities<'a>(categories: &[(&'a str, Vec<&str>)], scores: &[(&str, i32)]) -> Vec<Sensitivity<'a>> {
+    let mut result: Vec<Sensitivity> = categories
+        .iter()
+        .map(|(cat, labels)| {
+            let nets: Vec<i32> =
+                labels.iter().filter_map(|label| scores.iter().find(|(l, _)| l == label).map(|s| s.1)).collect();
+            let min_net = nets.iter().copied().min().unwrap_or(0);
+            let max_net = nets.iter().copied().max().unwrap_or(0);
+            Sensitivity { weight: cat, min_net, max_net, spread: max_net - min_net }
+        })
+        .collect();
+    result.sort_by_key(|s| std::cmp::Reverse(s.spread));
+    result
+}
+
 fn make_config(f: impl FnOnce(&mut Weights)) -> AiConfig {
     let mut config = medium_config();
     f(&mut config.weights);
@@ -124,8 +172,37 @@ fn make_config(f: impl FnOnce(&mut Weights)) -> AiConfig {
 }
 
 fn main() {
+    let mut adjudicate = false;
+    let mut sensitivity = false;
+    let mut json: Option<String> = None;
+    let mut threshold = DEFAULT_ADJUDICATION_THRESHOLD;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--adjudicate" => adjudicate = true,
+            "--sensitivity" => sensitivity = true,
+            "--json" => json = Some(args.next().unwrap_or_default()),
+            "--adjudicate-threshold" => {
+                let value = args.next().unwrap_or_default();
+                threshold = value.parse().unwrap_or_else(|_| {
+                    eprintln!("Invalid threshold '{value}' (expected pawns, e.g. 3.0)");
+                    std::process::exit(2);
+                });
+            }
+            _ => {
+                eprintln!("Usage: simulate [--adjudicate] [--adjudicate-threshold <pawns>] [--sensitivity [--json <path>]]");
+                std::process::exit(2);
+            }
+        }
+    }
+    let adjudicate = adjudicate.then_some(threshold);
+
     println!("=== Chess AI Weight Optimization (medium: d1 auto-deepen 25k) ===");
-    println!("Games per matchup: {GAMES_PER_MATCHUP}, max moves per game: {MAX_MOVES}\n");
+    println!("Games per matchup: {GAMES_PER_MATCHUP}, max moves per game: {MAX_MOVES}");
+    match adjudicate {
+        Some(t) => println!("Games at the move cap are adjudicated by eval (threshold ±{t})\n"),
+        None => println!("Games at the move cap are draws\n"),
+    }
 
     let baseline = medium_config();
 
@@ -177,12 +254,12 @@ fn main() {
         }
         print!("  Testing {label}... ");
         std::io::stdout().flush().ok();
-        let result = run_matchup(label, config, "baseline", &baseline);
+        let result = run_matchup(label, config, "baseline", &baseline, adjudicate);
         let net = result.white_wins as i32 - result.black_wins as i32;
         scores.push((label, net));
     }
 
-    scores.sort_by(|a, b| b.1.cmp(&a.1));
+    scores.sort_by_key(|s| std::cmp::Reverse(s.1));
 
     println!("\n--- Phase 1 Rankings (net wins vs baseline) ---\n");
     for (label, net) in &scores {
@@ -196,9 +273,7 @@ fn main() {
         println!("  {indicator}{net:>3}  {label}");
     }
 
-    // Phase 2: combine the best from each category and test
-    println!("\n--- Phase 2: Combined best weights ---\n");
-
+    // Variations grouped by the weight they change
     let categories = [
         ("centre_atk", vec!["centre_atk=0.05", "centre_atk=0.3", "centre_atk=0.5"]),
         ("centre_occ", vec!["centre_occ=0.1", "centre_occ=0.4", "centre_occ=0.8"]),
@@ -210,6 +285,28 @@ fn main() {
         ("rep_pen", vec!["rep_pen=0.5", "rep_pen=5.0", "rep_pen=20.0"]),
     ];
 
+    if sensitivity {
+        let table = sensitivities(&categories, &scores);
+        println!("\n--- Weight sensitivity (spread of net wins vs baseline) ---\n");
+        println!("  {:<12} {:>6} {:>6} {:>6}", "weight", "min", "max", "spread");
+        for s in &table {
+            let note = if s.spread == 0 { "  (no effect seen)" } else { "" };
+            println!("  {:<12} {:>+6} {:>+6} {:>6}{note}", s.weight, s.min_net, s.max_net, s.spread);
+        }
+        if let Some(path) = json {
+            let text = serde_json::to_string_pretty(&table).expect("sensitivity table serializes");
+            if let Err(e) = std::fs::write(&path, text) {
+                eprintln!("Could not write {path}: {e}");
+                std::process::exit(1);
+            }
+            println!("\n  Wrote {path}");
+        }
+        return;
+    }
+
+    // Phase 2: combine the best from each category and test
+    println!("\n--- Phase 2: Combined best weights ---\n");
+
     let mut best_weights = Weights::default();
     println!("  Best per category (vs baseline):");
 
@@ -241,7 +338,7 @@ fn main() {
     println!("\n  Combined weights: {best_weights:?}");
     println!("\n  Testing combined vs baseline ({PHASE2_GAMES} games)...\n");
 
-    let result = run_matchup_n("combined", &combined, "baseline", &baseline_p2, PHASE2_GAMES);
+    let result = run_matchup_n("combined", &combined, "baseline", &baseline_p2, PHASE2_GAMES, adjudicate);
 
     println!("\n--- Final Result ---\n");
     println!(
@@ -1,6 +1,9 @@
+use std::sync::OnceLock;
+
 use serde::{Deserialize, Serialize};
 
-use crate::moves::Move;
+use crate::error::ChessError;
+use crate::moves::{index_to_square, square_to_index, Move};
 use crate::piece::{Color, Piece, PieceType};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -11,8 +14,294 @@ pub struct CastlingRights {
     pub black_queenside: bool,
 }
 
+impl CastlingRights {
+    /// The rights as in FEN: some of "KQkq" in that order, or "-" for none.
+    pub fn to_fen(&self) -> String {
+        let rights: String = [
+            (self.white_kingside, 'K'),
+            (self.white_queenside, 'Q'),
+            (self.black_kingside, 'k'),
+            (self.black_queenside, 'q'),
+        ]
+        .iter()
+        .filter(|(allowed, _)| *allowed)
+        .map(|(_, c)| *c)
+        .collect();
+        if rights.is_empty() { "-".to_string() } else { rights }
+    }
+}
+
+/// Halfmoves without a pawn move or capture after which the game is drawn.
+pub const FIFTY_MOVE_HALFMOVES: u32 = 100;
+
+/// Largest halfmove clock `Board::from_fen` accepts: the game is drawn
+/// outright at 75 moves without a pawn move or capture, so no real game
+/// reaches a higher count.
+pub const MAX_FEN_HALFMOVE_CLOCK: u32 = 150;
+
+/// Largest fullmove number `Board::from_fen` accepts, comfortably beyond
+/// the longest game the fifty-move rule allows (under 6,000 moves).
+pub const MAX_FEN_FULLMOVE_NUMBER: u32 = 10_000;
+
+/// `Board::game_phase` at or below which the position counts as an
+/// endgame: at most a rook and a minor piece each, or two rooks each (or an
+/// equivalent queen).
+pub const ENDGAME_PHASE: f64 = 1.0 / 3.0;
+
+/// The four central squares: d4, d5, e4, e5.
+pub const CENTRE_SQUARES: [(usize, usize); 4] = [(3, 3), (3, 4), (4, 3), (4, 4)];
+
+/// Which ruleset the game is played under.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Standard chess, plus a king reaching one of `CENTRE_SQUARES` wins.
+    KingOfTheHill,
+}
+
+/// Why no further moves can be played from a position.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum TerminalReason {
+    Checkmate,
+    /// King of the Hill: the opponent's king reached the centre.
+    KingOfTheHill,
+    Stalemate,
+    FiftyMoveRule,
+    Repetition,
+    InsufficientMaterial,
+}
+
+/// How a finished game ended.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct GameResult {
+    /// `None` for a draw.
+    pub winner: Option<Color>,
+    pub reason: TerminalReason,
+}
+
+/// Outcome of `Board::play`.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+pub struct PlayResult {
+    /// False if the move was illegal (or the game was already over) and the
+    /// board is unchanged.
+    pub ok: bool,
+    pub game_over: bool,
+    pub result: Option<GameResult>,
+    pub was_capture: bool,
+    /// The move gave check (including checkmate).
+    pub was_check: bool,
+}
+
+/// Ways a position can be illegal yet still playable, from `Board::validate`.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum PositionWarning {
+    /// The side that just moved is in check.
+    OpponentInCheck,
+    /// A pawn stands on the first or eighth rank, at (row, col).
+    PawnOnBackRank((usize, usize)),
+    /// More than eight pawns of one color.
+    TooManyPawns(Color),
+    MissingKing(Color),
+    /// More than one king of one color.
+    DuplicateKings(Color),
+}
+
+/// A simple tactical pattern on the board, from `Board::tactical_motifs`.
+/// Squares are `(row, col)`; `attacker` is always the motif's owner.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Motif {
+    /// A slider attacks `pinned`, which shields the more valuable `behind`
+    /// (the king, for an absolute pin) on the same line.
+    Pin { attacker: (usize, usize), pinned: (usize, usize), behind: (usize, usize) },
+    /// One piece attacks two or more `targets`: the king, or pieces that are
+    /// worth more than it or undefended.
+    Fork { attacker: (usize, usize), targets: Vec<(usize, usize)> },
+    /// A slider attacks `front`, which is worth more than `behind` on the
+    /// same line: once `front` moves, `behind` falls.
+    Skewer { attacker: (usize, usize), front: (usize, usize), behind: (usize, usize) },
+}
+
+/// Per-color count of pieces attacking each square, so attack queries are
+/// table reads. A board builds it on the first query and `apply_move` keeps
+/// it up to date from then on; `Board::set_square` drops it, to be rebuilt
+/// by the next query. A write straight to `Board::squares` after a query
+/// goes unseen: debug builds keep the squares the map describes and panic
+/// on the next query.
+#[derive(Clone, Debug, Default)]
+pub struct AttackMap {
+    counts: [[[u8; 8]; 8]; 2],
+    #[cfg(debug_assertions)]
+    squares: [[Option<Piece>; 8]; 8],
+}
+
+impl AttackMap {
+    fn color_index(color: Color) -> usize {
+        if color == Color::White { 0 } else { 1 }
+    }
+
+    /// Build the map from scratch.
+    pub fn compute(board: &Board) -> AttackMap {
+        let mut map = AttackMap {
+            #[cfg(debug_assertions)]
+            squares: board.squares,
+            ..AttackMap::default()
+        };
+        for r in 0..8 {
+            for c in 0..8 {
+                if let Some(p) = board.squares[r][c] {
+                    let counts = &mut map.counts[Self::color_index(p.color)];
+                    board.for_each_attack_from(r, c, |tr, tc| counts[tr][tc] += 1);
+                }
+            }
+        }
+        map
+    }
+
+    /// Number of `color`'s pieces attacking (row, col).
+    pub fn count(&self, row: usize, col: usize, color: Color) -> u8 {
+        self.counts[Self::color_index(color)][row][col]
+    }
+}
+
+/// Per-color piece counts by type, gathered in a single board scan.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PieceCounts {
+    counts: [[u32; 6]; 2],
+}
+
+impl PieceCounts {
+    fn color_index(color: Color) -> usize {
+        if color == Color::White { 0 } else { 1 }
+    }
+
+    /// Number of `color`'s pieces of type `pt`.
+    pub fn get(&self, color: Color, pt: PieceType) -> u32 {
+        self.counts[Self::color_index(color)][pt.index()]
+    }
+
+    /// Total number of `color`'s pieces, king included.
+    pub fn total(&self, color: Color) -> u32 {
+        self.counts[Self::color_index(color)].iter().sum()
+    }
+
+    /// Number of `color`'s knights and bishops.
+    pub fn minors(&self, color: Color) -> u32 {
+        self.get(color, PieceType::Knight) + self.get(color, PieceType::Bishop)
+    }
+}
+
+/// Seed for the built-in Zobrist key table used by `Board::position_hash`.
+pub const DEFAULT_ZOBRIST_SEED: u64 = 0x5eed_c0de_2024_0001;
+
+/// `position_hash` of the standard start position with the default seed.
+/// Books and tables keyed by hash can check it to confirm they were built
+/// with a compatible hashing scheme.
+pub const START_POSITION_HASH: u64 = 0x6c3c_4bd5_fd75_eb03;
+
+/// Zobrist key table: one random key per (piece, square), plus keys for the
+/// side to move, each castling right and each en passant file. Keys are
+/// generated with SplitMix64 from a seed, so a table is fully reproducible.
+#[derive(Clone, Debug)]
+pub struct Zobrist {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+/// One SplitMix64 step: returns the advanced state and the next output.
+pub(crate) const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (state, z ^ (z >> 31))
+}
+
+impl Zobrist {
+    /// Build the key table for `seed`.
+    pub const fn with_seed(seed: u64) -> Zobrist {
+        let mut state = seed;
+        let mut pieces = [[0u64; 64]; 12];
+        let mut p = 0;
+        while p < 12 {
+            let mut sq = 0;
+            while sq < 64 {
+                let (next, key) = splitmix64(state);
+                state = next;
+                pieces[p][sq] = key;
+                sq += 1;
+            }
+            p += 1;
+        }
+        let (next, black_to_move) = splitmix64(state);
+        state = next;
+        let mut castling = [0u64; 4];
+        let mut i = 0;
+        while i < 4 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            castling[i] = key;
+            i += 1;
+        }
+        let mut en_passant = [0u64; 8];
+        let mut f = 0;
+        while f < 8 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            en_passant[f] = key;
+            f += 1;
+        }
+        Zobrist { pieces, black_to_move, castling, en_passant }
+    }
+
+    fn piece_index(p: Piece) -> usize {
+        let type_index = p.piece_type.index();
+        if p.color == Color::White { type_index } else { type_index + 6 }
+    }
+
+    /// Hash `board` with this key table.
+    pub fn hash(&self, board: &Board) -> u64 {
+        let mut hash: u64 = 0;
+        for r in 0..8 {
+            for c in 0..8 {
+                if let Some(p) = board.squares[r][c] {
+                    hash ^= self.pieces[Self::piece_index(p)][r * 8 + c];
+                }
+            }
+        }
+        if board.current_turn == Color::Black { hash ^= self.black_to_move; }
+        if board.castling_rights.white_kingside { hash ^= self.castling[0]; }
+        if board.castling_rights.white_queenside { hash ^= self.castling[1]; }
+        if board.castling_rights.black_kingside { hash ^= self.castling[2]; }
+        if board.castling_rights.black_queenside { hash ^= self.castling[3]; }
+        if let Some((_, c)) = board.en_passant_target {
+            hash ^= self.en_passant[c];
+        }
+        hash
+    }
+
+    /// Hash of the pawns alone, for caching pawn-structure evaluation.
+    pub fn pawn_hash(&self, board: &Board) -> u64 {
+        let mut hash: u64 = 0;
+        for r in 0..8 {
+            for c in 0..8 {
+                if let Some(p) = board.squares[r][c].filter(|p| p.piece_type == PieceType::Pawn) {
+                    hash ^= self.pieces[Self::piece_index(p)][r * 8 + c];
+                }
+            }
+        }
+        hash
+    }
+}
+
+static DEFAULT_ZOBRIST: Zobrist = Zobrist::with_seed(DEFAULT_ZOBRIST_SEED);
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Board {
+    /// Once the board has answered an attack query, edit through
+    /// `set_square`, which keeps the attack map in step.
     pub squares: [[Option<Piece>; 8]; 8],
     pub current_turn: Color,
     pub castling_rights: CastlingRights,
@@ -25,6 +314,11 @@ pub struct Board {
     pub captured_black: Vec<PieceType>,
     pub last_move: Option<((usize, usize), (usize, usize))>,
     pub position_history: Vec<u64>,
+    #[serde(default)]
+    pub variant: Variant,
+    /// Attack counts, built on demand (see `AttackMap`).
+    #[serde(skip)]
+    attacks: OnceLock<AttackMap>,
 }
 
 impl Default for Board {
@@ -36,7 +330,7 @@ impl Default for Board {
 impl Board {
     /// Create an empty board with no pieces. Useful for setting up test positions.
     pub fn empty() -> Self {
-        let board = Board {
+        Board {
             squares: [[None; 8]; 8],
             current_turn: Color::White,
             castling_rights: CastlingRights {
@@ -54,8 +348,9 @@ impl Board {
             captured_black: Vec::new(),
             last_move: None,
             position_history: Vec::new(),
-        };
-        board
+            variant: Variant::Standard,
+            attacks: OnceLock::new(),
+        }
     }
 
     pub fn new() -> Self {
@@ -105,6 +400,8 @@ impl Board {
             captured_black: Vec::new(),
             last_move: None,
             position_history: Vec::new(),
+            variant: Variant::Standard,
+            attacks: OnceLock::new(),
         };
         board.position_history.push(board.position_hash());
         board
@@ -127,7 +424,56 @@ impl Board {
         None
     }
 
+    /// Put `piece` on (row, col), or clear it with `None`.
+    pub fn set_square(&mut self, row: usize, col: usize, piece: Option<Piece>) {
+        self.squares[row][col] = piece;
+        self.attacks.take();
+    }
+
+    /// The attack map, built now if no query has needed it since the last
+    /// `set_square`.
+    fn attack_map(&self) -> &AttackMap {
+        let map = self.attacks.get_or_init(|| AttackMap::compute(self));
+        #[cfg(debug_assertions)]
+        self.assert_attack_map_current(map);
+        map
+    }
+
+    /// Debug builds only: panic if `squares` was written directly since
+    /// `map` was built, rather than answer attack queries from a stale map.
+    #[cfg(debug_assertions)]
+    fn assert_attack_map_current(&self, map: &AttackMap) {
+        assert!(map.squares == self.squares, "`squares` edited directly after an attack query; use `set_square`");
+    }
+
+    /// Number of `color`'s pieces attacking (row, col).
+    pub fn attack_count(&self, row: usize, col: usize, color: Color) -> u32 {
+        self.attack_map().count(row, col, color) as u32
+    }
+
+    /// True if a pawn of `color` attacks (row, col), i.e. stands diagonally
+    /// behind it from `color`'s side. Pawns on the a and h files have only
+    /// one such diagonal.
+    pub fn pawn_attacks_square(&self, row: usize, col: usize, color: Color) -> bool {
+        let pawn_row = match color {
+            Color::White => row.checked_sub(1),
+            Color::Black => Some(row + 1).filter(|&r| r < 8),
+        };
+        let Some(pawn_row) = pawn_row else {
+            return false;
+        };
+        [col.wrapping_sub(1), col + 1].into_iter().any(|c| {
+            c < 8 && self.squares[pawn_row][c].is_some_and(|p| p.piece_type == PieceType::Pawn && p.color == color)
+        })
+    }
+
     pub fn is_square_attacked_by(&self, row: usize, col: usize, attacker: Color) -> bool {
+        self.attack_map().count(row, col, attacker) > 0
+    }
+
+    /// `is_square_attacked_by` without the attack map: scan outwards from
+    /// the square for attackers.
+    fn scan_attacked_by(&self, row: usize, col: usize, attacker: Color) -> bool {
         // Check knight attacks
         let knight_offsets: [(i32, i32); 8] = [
             (-2, -1), (-2, 1), (-1, -2), (-1, 2),
@@ -218,185 +564,663 @@ impl Board {
         false
     }
 
-    pub fn is_in_check(&self, color: Color) -> bool {
-        if let Some((kr, kc)) = self.find_king(color) {
-            self.is_square_attacked_by(kr, kc, color.opposite())
-        } else {
-            false
-        }
+    /// Squares attacked by the piece on (row, col), whatever occupies them.
+    /// Pawns attack diagonally forward only; castling is not an attack.
+    pub fn attacks_from(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut attacks = Vec::new();
+        self.for_each_attack_from(row, col, |r, c| attacks.push((r, c)));
+        attacks
     }
 
-    pub fn position_hash(&self) -> u64 {
-        let mut hash: u64 = 0;
-        for r in 0..8 {
-            for c in 0..8 {
-                if let Some(p) = self.squares[r][c] {
-                    let piece_val = match p.piece_type {
-                        PieceType::Pawn => 1u64,
-                        PieceType::Knight => 2,
-                        PieceType::Bishop => 3,
-                        PieceType::Rook => 4,
-                        PieceType::Queen => 5,
-                        PieceType::King => 6,
-                    };
-                    let color_val = if p.color == Color::White { 0u64 } else { 7u64 };
-                    let sq_val = piece_val + color_val;
-                    hash ^= sq_val.wrapping_mul(0x9e3779b97f4a7c15u64.wrapping_add(((r * 8 + c) as u64).wrapping_mul(0x517cc1b727220a95)));
+    /// Call `f` on each square `attacks_from` would return, without
+    /// allocating.
+    fn for_each_attack_from(&self, row: usize, col: usize, mut f: impl FnMut(usize, usize)) {
+        let piece = match self.squares[row][col] {
+            Some(p) => p,
+            None => return,
+        };
+        let mut push = |r: i32, c: i32| {
+            if Self::in_bounds(r, c) {
+                f(r as usize, c as usize);
+            }
+        };
+        let (r0, c0) = (row as i32, col as i32);
+        match piece.piece_type {
+            PieceType::Pawn => {
+                let dir = if piece.color == Color::White { 1 } else { -1 };
+                push(r0 + dir, c0 - 1);
+                push(r0 + dir, c0 + 1);
+            }
+            PieceType::Knight => {
+                for (dr, dc) in [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)] {
+                    push(r0 + dr, c0 + dc);
+                }
+            }
+            PieceType::King => {
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        if dr != 0 || dc != 0 {
+                            push(r0 + dr, c0 + dc);
+                        }
+                    }
+                }
+            }
+            PieceType::Bishop | PieceType::Rook | PieceType::Queen => {
+                let straight = piece.piece_type != PieceType::Bishop;
+                let diagonal = piece.piece_type != PieceType::Rook;
+                let dirs: [(i32, i32); 8] = [
+                    (0, 1), (0, -1), (1, 0), (-1, 0),
+                    (1, 1), (1, -1), (-1, 1), (-1, -1),
+                ];
+                for (i, (dr, dc)) in dirs.iter().enumerate() {
+                    if (i < 4 && !straight) || (i >= 4 && !diagonal) {
+                        continue;
+                    }
+                    let mut r = r0 + dr;
+                    let mut c = c0 + dc;
+                    while Self::in_bounds(r, c) {
+                        push(r, c);
+                        if self.squares[r as usize][c as usize].is_some() {
+                            break;
+                        }
+                        r += dr;
+                        c += dc;
+                    }
                 }
             }
         }
-        if self.current_turn == Color::Black { hash ^= 0xdeadbeefcafe1234; }
-        if self.castling_rights.white_kingside { hash ^= 0x1; }
-        if self.castling_rights.white_queenside { hash ^= 0x2; }
-        if self.castling_rights.black_kingside { hash ^= 0x4; }
-        if self.castling_rights.black_queenside { hash ^= 0x8; }
-        if let Some((r, c)) = self.en_passant_target {
-            hash ^= (r as u64 * 8 + c as u64).wrapping_mul(0xabcdef0123456789);
-        }
-        hash
     }
 
-    pub fn is_threefold_repetition(&self) -> bool {
-        if self.position_history.len() < 5 {
-            return false;
+    /// Sliding pieces (of either color) whose rays reach `(row, col)`: the
+    /// first piece in each direction, if it slides that way.
+    fn sliders_reaching(&self, row: usize, col: usize, out: &mut Vec<(usize, usize)>) {
+        let dirs: [(i32, i32); 8] = [
+            (0, 1), (0, -1), (1, 0), (-1, 0),
+            (1, 1), (1, -1), (-1, 1), (-1, -1),
+        ];
+        for (i, (dr, dc)) in dirs.iter().enumerate() {
+            let (mut r, mut c) = (row as i32 + dr, col as i32 + dc);
+            while Self::in_bounds(r, c) {
+                if let Some(p) = self.squares[r as usize][c as usize] {
+                    let slides = match p.piece_type {
+                        PieceType::Queen => true,
+                        PieceType::Rook => i < 4,
+                        PieceType::Bishop => i >= 4,
+                        _ => false,
+                    };
+                    if slides && !out.contains(&(r as usize, c as usize)) {
+                        out.push((r as usize, c as usize));
+                    }
+                    break;
+                }
+                r += dr;
+                c += dc;
+            }
         }
-        let current = self.position_hash();
-        let count = self.position_history.iter().filter(|&&h| h == current).count();
-        count >= 3 // current position is already in history, so 3 entries = 3 occurrences
     }
 
-    pub fn has_insufficient_material(&self) -> bool {
-        let mut white_pieces = Vec::new();
-        let mut black_pieces = Vec::new();
+    /// Squares of `color`'s pieces that attack (row, col).
+    pub fn attackers_of(&self, row: usize, col: usize, color: Color) -> Vec<(usize, usize)> {
+        let mut attackers = Vec::new();
         for r in 0..8 {
             for c in 0..8 {
-                if let Some(p) = self.squares[r][c] {
-                    match p.color {
-                        Color::White => white_pieces.push(p.piece_type),
-                        Color::Black => black_pieces.push(p.piece_type),
-                    }
+                if matches!(self.squares[r][c], Some(p) if p.color == color)
+                    && self.attacks_from(r, c).contains(&(row, col))
+                {
+                    attackers.push((r, c));
                 }
             }
         }
-        // King vs King
-        if white_pieces.len() == 1 && black_pieces.len() == 1 {
-            return true;
-        }
-        // King+minor vs King
-        if white_pieces.len() == 1 && black_pieces.len() == 2
-            && black_pieces.iter().any(|&pt| pt == PieceType::Bishop || pt == PieceType::Knight)
-        {
-            return true;
-        }
-        if black_pieces.len() == 1 && white_pieces.len() == 2
-            && white_pieces.iter().any(|&pt| pt == PieceType::Bishop || pt == PieceType::Knight)
-        {
-            return true;
-        }
-        false
+        attackers
     }
 
-    pub fn generate_moves(&self, color: Color) -> Vec<Move> {
-        let mut moves = Vec::new();
+    /// Static exchange evaluation: material `by` wins (in pawns) by capturing
+    /// the piece on (row, col) and then trading off on that square, each side
+    /// recapturing with its least valuable attacker and stopping when that
+    /// would lose material. `None` if `by` has no capture there. Pins are
+    /// ignored.
+    pub fn see(&self, row: usize, col: usize, by: Color) -> Option<f64> {
+        // A king may only recapture last, so it is priced above everything.
+        let exchange_value = |pt: PieceType| if pt == PieceType::King { 100.0 } else { pt.value() };
+        let target = self.squares[row][col]?;
+        let (fr, fc) = self
+            .attackers_of(row, col, by)
+            .into_iter()
+            .min_by(|&(ar, ac), &(br, bc)| {
+                let value = |r: usize, c: usize| self.squares[r][c].map_or(0.0, |p| exchange_value(p.piece_type));
+                value(ar, ac).total_cmp(&value(br, bc))
+            })?;
+        let mut after = self.clone();
+        after.set_square(row, col, after.squares[fr][fc]);
+        after.set_square(fr, fc, None);
+        let reply = after.see(row, col, by.opposite()).map_or(0.0, |g| g.max(0.0));
+        Some(exchange_value(target.piece_type) - reply)
+    }
 
-        for row in 0..8usize {
-            for col in 0..8usize {
-                if let Some(piece) = self.squares[row][col] {
-                    if piece.color != color {
-                        continue;
-                    }
-                    match piece.piece_type {
-                        PieceType::Pawn => self.generate_pawn_moves(row, col, color, &mut moves),
-                        PieceType::Knight => self.generate_knight_moves(row, col, color, &mut moves),
-                        PieceType::Bishop => self.generate_bishop_moves(row, col, color, &mut moves),
-                        PieceType::Rook => self.generate_rook_moves(row, col, color, &mut moves),
-                        PieceType::Queen => self.generate_queen_moves(row, col, color, &mut moves),
-                        PieceType::King => self.generate_king_moves(row, col, color, &mut moves),
-                    }
+    /// `color`'s pieces (other than the king) that the opponent can win
+    /// material by capturing, by static exchange evaluation.
+    pub fn hanging_pieces(&self, color: Color) -> Vec<(usize, usize)> {
+        let mut hanging = Vec::new();
+        for row in 0..8 {
+            for col in 0..8 {
+                let is_target = matches!(self.squares[row][col], Some(p) if p.color == color && p.piece_type != PieceType::King);
+                if is_target && self.see(row, col, color.opposite()).is_some_and(|gain| gain > 0.0) {
+                    hanging.push((row, col));
                 }
             }
         }
-
-        moves
+        hanging
     }
 
-    fn generate_pawn_moves(&self, row: usize, col: usize, color: Color, moves: &mut Vec<Move>) {
-        let (dir, start_row, promo_row): (i32, usize, usize) = match color {
-            Color::White => (1, 1, 7),
-            Color::Black => (-1, 6, 0),
-        };
-
-        let forward = row as i32 + dir;
-
-        // Single push
-        if Self::in_bounds(forward, col as i32) && self.squares[forward as usize][col].is_none() {
-            if forward as usize == promo_row {
-                for pt in &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
-                    moves.push(Move {
-                        from: (row, col),
-                        to: (forward as usize, col),
-                        promotion: Some(*pt),
-                    });
+    /// Pins, forks and skewers that `color`'s pieces have on the board right
+    /// now. Ask for the other colour to find those `color` is subject to.
+    /// Detection is deliberately conservative: line motifs need two enemy
+    /// pieces in a row with nothing between, and fork targets must be the
+    /// king, worth more than the forking piece, or undefended.
+    pub fn tactical_motifs(&self, color: Color) -> Vec<Motif> {
+        // The king outranks everything: a pin against it is absolute.
+        let worth = |pt: PieceType| if pt == PieceType::King { 100.0 } else { pt.value() };
+        let enemy = color.opposite();
+        let mut motifs = Vec::new();
+        for row in 0..8 {
+            for col in 0..8 {
+                let Some(piece) = self.squares[row][col].filter(|p| p.color == color) else {
+                    continue;
+                };
+                let targets: Vec<(usize, usize)> = self
+                    .attacks_from(row, col)
+                    .into_iter()
+                    .filter(|&(r, c)| {
+                        self.squares[r][c].is_some_and(|t| {
+                            t.color == enemy
+                                && (t.piece_type == PieceType::King
+                                    || worth(t.piece_type) > worth(piece.piece_type)
+                                    || self.attack_count(r, c, enemy) == 0)
+                        })
+                    })
+                    .collect();
+                if targets.len() >= 2 {
+                    motifs.push(Motif::Fork { attacker: (row, col), targets });
                 }
-            } else {
-                moves.push(Move {
-                    from: (row, col),
-                    to: (forward as usize, col),
-                    promotion: None,
-                });
 
-                // Double push
-                if row == start_row {
-                    let double = forward + dir;
-                    if Self::in_bounds(double, col as i32)
-                        && self.squares[double as usize][col].is_none()
-                    {
-                        moves.push(Move {
-                            from: (row, col),
-                            to: (double as usize, col),
-                            promotion: None,
-                        });
+                let (straight, diagonal) = match piece.piece_type {
+                    PieceType::Rook => (true, false),
+                    PieceType::Bishop => (false, true),
+                    PieceType::Queen => (true, true),
+                    _ => continue,
+                };
+                let dirs: [(i32, i32); 8] = [
+                    (0, 1), (0, -1), (1, 0), (-1, 0),
+                    (1, 1), (1, -1), (-1, 1), (-1, -1),
+                ];
+                for (i, (dr, dc)) in dirs.iter().enumerate() {
+                    if (i < 4 && !straight) || (i >= 4 && !diagonal) {
+                        continue;
+                    }
+                    // The first two pieces along the ray, if both are enemies.
+                    let mut hits = Vec::new();
+                    let (mut r, mut c) = (row as i32 + dr, col as i32 + dc);
+                    while Self::in_bounds(r, c) && hits.len() < 2 {
+                        if let Some(p) = self.squares[r as usize][c as usize] {
+                            hits.push(((r as usize, c as usize), p));
+                        }
+                        r += dr;
+                        c += dc;
+                    }
+                    let [(front, f), (behind, b)] = hits[..] else {
+                        continue;
+                    };
+                    if f.color != enemy || b.color != enemy {
+                        continue;
+                    }
+                    let attacker = (row, col);
+                    if worth(b.piece_type) > worth(f.piece_type) {
+                        motifs.push(Motif::Pin { attacker, pinned: front, behind });
+                    } else if worth(f.piece_type) > worth(b.piece_type) {
+                        motifs.push(Motif::Skewer { attacker, front, behind });
                     }
                 }
             }
         }
+        motifs
+    }
 
-        // Captures (including en passant)
-        for dc in &[-1i32, 1] {
-            let nc = col as i32 + dc;
-            if !Self::in_bounds(forward, nc) {
-                continue;
-            }
-            let tr = forward as usize;
-            let tc = nc as usize;
+    pub fn is_in_check(&self, color: Color) -> bool {
+        if let Some((kr, kc)) = self.find_king(color) {
+            self.is_square_attacked_by(kr, kc, color.opposite())
+        } else {
+            false
+        }
+    }
 
-            let is_capture = self.squares[tr][tc]
-                .map(|p| p.color != color)
-                .unwrap_or(false);
-            let is_en_passant = self.en_passant_target == Some((tr, tc));
+    /// King of the Hill: true if `color`'s king stands on a centre square.
+    /// Always false in other variants.
+    pub fn king_on_hill(&self, color: Color) -> bool {
+        self.variant == Variant::KingOfTheHill
+            && self.find_king(color).is_some_and(|k| CENTRE_SQUARES.contains(&k))
+    }
 
-            if is_capture || is_en_passant {
-                if tr == promo_row {
-                    for pt in &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
-                        moves.push(Move {
-                            from: (row, col),
-                            to: (tr, tc),
-                            promotion: Some(*pt),
-                        });
-                    }
-                } else {
-                    moves.push(Move {
-                        from: (row, col),
-                        to: (tr, tc),
-                        promotion: None,
-                    });
-                }
-            }
+    /// The winning side if the game has been decided by `apply_move`.
+    pub fn winner(&self) -> Option<Color> {
+        match self.result.as_deref() {
+            Some("White wins") => Some(Color::White),
+            Some("Black wins") => Some(Color::Black),
+            _ => None,
         }
     }
 
-    fn generate_knight_moves(&self, row: usize, col: usize, color: Color, moves: &mut Vec<Move>) {
-        let offsets: [(i32, i32); 8] = [
+    /// Halfmoves played since the start of the game, from `fullmove_number`
+    /// and the side to move alone: 0 at the start, 1 after 1.e4. For a game
+    /// played from the start position this is `position_history.len() - 1`;
+    /// a position set up without its history (e.g. from FEN) still knows
+    /// its ply.
+    pub fn ply(&self) -> u32 {
+        let black = if self.current_turn == Color::Black { 1 } else { 0 };
+        self.fullmove_number.saturating_sub(1) * 2 + black
+    }
+
+    /// Reversible halfmoves left before the 50-move rule draws the game.
+    pub fn moves_until_fifty_move_draw(&self) -> u32 {
+        FIFTY_MOVE_HALFMOVES.saturating_sub(self.halfmove_clock)
+    }
+
+    /// True if `self.current_turn` has no legal moves but is not in check.
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check(self.current_turn)
+            && self.legal_move_count(self.current_turn) == 0
+    }
+
+    /// The rule that ends the game in this position, if any. Checked in the
+    /// same order as `apply_move` applies them.
+    pub fn terminal_reason(&self) -> Option<TerminalReason> {
+        if self.king_on_hill(self.current_turn.opposite()) {
+            return Some(TerminalReason::KingOfTheHill);
+        }
+        if self.legal_move_count(self.current_turn) == 0 {
+            return Some(if self.is_in_check(self.current_turn) {
+                TerminalReason::Checkmate
+            } else {
+                TerminalReason::Stalemate
+            });
+        }
+        if self.halfmove_clock >= FIFTY_MOVE_HALFMOVES {
+            return Some(TerminalReason::FiftyMoveRule);
+        }
+        if self.is_threefold_repetition() {
+            return Some(TerminalReason::Repetition);
+        }
+        if self.has_insufficient_material() {
+            return Some(TerminalReason::InsufficientMaterial);
+        }
+        None
+    }
+
+    /// Zobrist hash of the position (pieces, side to move, castling rights and
+    /// en passant file) using the built-in key table. Values are stable for
+    /// `DEFAULT_ZOBRIST_SEED`, so they can key external tables and books.
+    pub fn position_hash(&self) -> u64 {
+        DEFAULT_ZOBRIST.hash(self)
+    }
+
+    /// Zobrist hash of the pawn placement only (built-in key table): equal
+    /// for any two positions with the same pawns on the same squares.
+    pub fn pawn_hash(&self) -> u64 {
+        DEFAULT_ZOBRIST.pawn_hash(self)
+    }
+
+    /// Zobrist hash of the position using a caller-supplied key table.
+    pub fn position_hash_with(&self, keys: &Zobrist) -> u64 {
+        keys.hash(self)
+    }
+
+    /// The same position with colours swapped and ranks flipped: White's
+    /// pieces become Black's on the mirrored squares, and the other side is
+    /// to move. Castling rights and the en passant square follow. The
+    /// position history cannot be mirrored, so it restarts at this position.
+    pub fn mirror(&self) -> Board {
+        let mut squares = [[None; 8]; 8];
+        for (r, row) in self.squares.iter().enumerate() {
+            for (c, sq) in row.iter().enumerate() {
+                squares[7 - r][c] = sq.map(|p| Piece::new(p.piece_type, p.color.opposite()));
+            }
+        }
+        let flip = |(r, c): (usize, usize)| (7 - r, c);
+        let mut board = Board {
+            squares,
+            current_turn: self.current_turn.opposite(),
+            castling_rights: CastlingRights {
+                white_kingside: self.castling_rights.black_kingside,
+                white_queenside: self.castling_rights.black_queenside,
+                black_kingside: self.castling_rights.white_kingside,
+                black_queenside: self.castling_rights.white_queenside,
+            },
+            en_passant_target: self.en_passant_target.map(flip),
+            captured_white: self.captured_black.clone(),
+            captured_black: self.captured_white.clone(),
+            last_move: self.last_move.map(|(from, to)| (flip(from), flip(to))),
+            position_history: Vec::new(),
+            attacks: OnceLock::new(),
+            ..self.clone()
+        };
+        board.position_history.push(board.position_hash());
+        board
+    }
+
+    /// Hash of the colour-mirrored position (see `mirror`).
+    pub fn color_mirror_hash(&self) -> u64 {
+        self.mirror().position_hash()
+    }
+
+    /// Key shared by a position and its colour mirror, so a book can store
+    /// one entry per mirror pair and find it from either side.
+    pub fn mirror_invariant_hash(&self) -> u64 {
+        self.position_hash().min(self.color_mirror_hash())
+    }
+
+    /// How many times the current position has occurred in the game,
+    /// counting now (so at least 1). Three is a repetition draw.
+    pub fn repetition_count(&self) -> usize {
+        let current = self.position_hash();
+        self.position_history.iter().filter(|&&h| h == current).count().max(1)
+    }
+
+    /// A copy for search, with `position_history` cut back to the positions
+    /// since the last capture or pawn move: nothing older can ever recur, so
+    /// repetition detection is unchanged, but clone cost no longer grows
+    /// with the length of the game.
+    pub fn clone_for_search(&self) -> Board {
+        let keep = (self.halfmove_clock as usize + 1).min(self.position_history.len());
+        Board {
+            squares: self.squares,
+            current_turn: self.current_turn,
+            castling_rights: self.castling_rights.clone(),
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            game_over: self.game_over,
+            result: self.result.clone(),
+            captured_white: self.captured_white.clone(),
+            captured_black: self.captured_black.clone(),
+            last_move: self.last_move,
+            position_history: self.position_history[self.position_history.len() - keep..].to_vec(),
+            variant: self.variant,
+            attacks: self.attacks.clone(),
+        }
+    }
+
+    /// The position with the other side to move, as if the side to move had
+    /// passed. Not a legal chess move: used to ask "what would they do here?"
+    /// (see `mate_threat_in`). Any en passant chance is forfeited.
+    pub fn null_move(&self) -> Board {
+        let mut board = self.clone_for_search();
+        if board.current_turn == Color::Black {
+            board.fullmove_number = board.fullmove_number.saturating_add(1);
+        }
+        board.current_turn = board.current_turn.opposite();
+        board.en_passant_target = None;
+        board.last_move = None;
+        board.position_history.push(board.position_hash());
+        board
+    }
+
+    /// A move that wins by force within `n` of the side to move's own moves,
+    /// by checkmate (or, in King of the Hill, reaching the centre), whatever
+    /// the defence. `None` if there is no such win or the game is over. The
+    /// search is exhaustive, so keep `n` small.
+    pub fn find_mate(&self, n: u32) -> Option<Move> {
+        if n == 0 || self.game_over {
+            return None;
+        }
+        let mover = self.current_turn;
+        self.generate_legal_moves(mover).into_iter().find(|m| {
+            let mut after = self.clone_for_search();
+            after.apply_move(m);
+            if after.game_over {
+                return after.winner() == Some(mover);
+            }
+            n > 1
+                && after.generate_legal_moves(after.current_turn).iter().all(|reply| {
+                    let mut defended = after.clone_for_search();
+                    defended.apply_move(reply);
+                    defended.find_mate(n - 1).is_some()
+                })
+        })
+    }
+
+    /// True if the side not to move would have a forced win in `n` (see
+    /// `find_mate`) were it their turn: a threat the side to move has to
+    /// parry. False when the side to move is in check, since passing would
+    /// leave the king en prise.
+    pub fn mate_threat_in(&self, n: u32) -> bool {
+        !self.game_over && !self.is_in_check(self.current_turn) && self.null_move().find_mate(n).is_some()
+    }
+
+    /// True if the opponent threatens mate in one, e.g. on a weak back rank.
+    pub fn opponent_has_mate_in_one(&self) -> bool {
+        self.mate_threat_in(1)
+    }
+
+    /// True if the current position has occurred at least once before.
+    pub fn is_repetition(&self) -> bool {
+        self.repetition_count() >= 2
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        if self.position_history.len() < 5 {
+            return false;
+        }
+        self.repetition_count() >= 3
+    }
+
+    /// Problems that make the position illegal but not unplayable, e.g. for
+    /// a hand-set-up position. Empty for any position reached by play.
+    pub fn validate(&self) -> Vec<PositionWarning> {
+        let mut warnings = Vec::new();
+        let counts = self.piece_counts();
+        for color in [Color::White, Color::Black] {
+            match counts.get(color, PieceType::King) {
+                0 => warnings.push(PositionWarning::MissingKing(color)),
+                1 => {}
+                _ => warnings.push(PositionWarning::DuplicateKings(color)),
+            }
+            if counts.get(color, PieceType::Pawn) > 8 {
+                warnings.push(PositionWarning::TooManyPawns(color));
+            }
+        }
+        for row in [0, 7] {
+            for col in 0..8 {
+                if matches!(self.squares[row][col], Some(p) if p.piece_type == PieceType::Pawn) {
+                    warnings.push(PositionWarning::PawnOnBackRank((row, col)));
+                }
+            }
+        }
+        if self.is_in_check(self.current_turn.opposite()) {
+            warnings.push(PositionWarning::OpponentInCheck);
+        }
+        warnings
+    }
+
+    /// Count every piece on the board by color and type.
+    pub fn piece_counts(&self) -> PieceCounts {
+        let mut counts = PieceCounts::default();
+        for row in &self.squares {
+            for p in row.iter().flatten() {
+                counts.counts[PieceCounts::color_index(p.color)][p.piece_type.index()] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Canonical code for the material on the board, e.g. "KQvK" or
+    /// "KRPvKR": each side's pieces from king down to pawns, the side with
+    /// more material first (on equal material, the side with the longer
+    /// code, so both colours give the same signature). For classifying
+    /// endgames.
+    pub fn material_signature(&self) -> String {
+        const ORDER: [PieceType; 6] =
+            [PieceType::King, PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::Pawn];
+        let counts = self.piece_counts();
+        let side = |color: Color| {
+            let code: String = ORDER
+                .iter()
+                .flat_map(|&pt| std::iter::repeat_n(pt.letter(), counts.get(color, pt) as usize))
+                .collect();
+            let material: f64 = ORDER.iter().map(|&pt| pt.value() * counts.get(color, pt) as f64).sum();
+            (material, code)
+        };
+        let (white, black) = (side(Color::White), side(Color::Black));
+        let white_first = (white.0, white.1.len(), &white.1) >= (black.0, black.1.len(), &black.1);
+        let (strong, weak) = if white_first { (white.1, black.1) } else { (black.1, white.1) };
+        format!("{strong}v{weak}")
+    }
+
+    /// Non-pawn material left on the board weighted N=B=1, R=2, Q=4, as a
+    /// fraction of the starting total: 1.0 in the opening, 0.0 with only
+    /// kings and pawns left.
+    pub fn game_phase(&self) -> f64 {
+        let counts = self.piece_counts();
+        let phase: u32 = [Color::White, Color::Black]
+            .iter()
+            .map(|&c| counts.minors(c) + 2 * counts.get(c, PieceType::Rook) + 4 * counts.get(c, PieceType::Queen))
+            .sum();
+        phase.min(24) as f64 / 24.0
+    }
+
+    /// True once `game_phase` has fallen to `ENDGAME_PHASE`, for terms that
+    /// only make sense before (or after) the middlegame.
+    pub fn is_endgame(&self) -> bool {
+        self.game_phase() <= ENDGAME_PHASE
+    }
+
+    /// True if neither side can win under this board's variant, so the game
+    /// is drawn. Only standard chess has such a rule: in King of the Hill a
+    /// bare king can still walk to the centre.
+    pub fn has_insufficient_material(&self) -> bool {
+        match self.variant {
+            Variant::Standard => self.has_insufficient_standard_material(),
+            Variant::KingOfTheHill => false,
+        }
+    }
+
+    fn has_insufficient_standard_material(&self) -> bool {
+        let counts = self.piece_counts();
+        let white = counts.total(Color::White);
+        let black = counts.total(Color::Black);
+        // King vs King
+        if white == 1 && black == 1 {
+            return true;
+        }
+        // King+minor vs King
+        if white == 1 && black == 2 && counts.minors(Color::Black) == 1 {
+            return true;
+        }
+        if black == 1 && white == 2 && counts.minors(Color::White) == 1 {
+            return true;
+        }
+        false
+    }
+
+    pub fn generate_moves(&self, color: Color) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for row in 0..8usize {
+            for col in 0..8usize {
+                if let Some(piece) = self.squares[row][col] {
+                    if piece.color != color {
+                        continue;
+                    }
+                    match piece.piece_type {
+                        PieceType::Pawn => self.generate_pawn_moves(row, col, color, &mut moves),
+                        PieceType::Knight => self.generate_knight_moves(row, col, color, &mut moves),
+                        PieceType::Bishop => self.generate_bishop_moves(row, col, color, &mut moves),
+                        PieceType::Rook => self.generate_rook_moves(row, col, color, &mut moves),
+                        PieceType::Queen => self.generate_queen_moves(row, col, color, &mut moves),
+                        PieceType::King => self.generate_king_moves(row, col, color, &mut moves),
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    fn generate_pawn_moves(&self, row: usize, col: usize, color: Color, moves: &mut Vec<Move>) {
+        let (dir, start_row, promo_row): (i32, usize, usize) = match color {
+            Color::White => (1, 1, 7),
+            Color::Black => (-1, 6, 0),
+        };
+
+        let forward = row as i32 + dir;
+
+        // Single push
+        if Self::in_bounds(forward, col as i32) && self.squares[forward as usize][col].is_none() {
+            if forward as usize == promo_row {
+                for pt in &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                    moves.push(Move {
+                        from: (row, col),
+                        to: (forward as usize, col),
+                        promotion: Some(*pt),
+                    });
+                }
+            } else {
+                moves.push(Move {
+                    from: (row, col),
+                    to: (forward as usize, col),
+                    promotion: None,
+                });
+
+                // Double push
+                if row == start_row {
+                    let double = forward + dir;
+                    if Self::in_bounds(double, col as i32)
+                        && self.squares[double as usize][col].is_none()
+                    {
+                        moves.push(Move {
+                            from: (row, col),
+                            to: (double as usize, col),
+                            promotion: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Captures (including en passant)
+        for dc in &[-1i32, 1] {
+            let nc = col as i32 + dc;
+            if !Self::in_bounds(forward, nc) {
+                continue;
+            }
+            let tr = forward as usize;
+            let tc = nc as usize;
+
+            let is_capture = self.squares[tr][tc]
+                .map(|p| p.color != color)
+                .unwrap_or(false);
+            let is_en_passant = self.en_passant_target == Some((tr, tc));
+
+            if is_capture || is_en_passant {
+                if tr == promo_row {
+                    for pt in &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                        moves.push(Move {
+                            from: (row, col),
+                            to: (tr, tc),
+                            promotion: Some(*pt),
+                        });
+                    }
+                } else {
+                    moves.push(Move {
+                        from: (row, col),
+                        to: (tr, tc),
+                        promotion: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn generate_knight_moves(&self, row: usize, col: usize, color: Color, moves: &mut Vec<Move>) {
+        let offsets: [(i32, i32); 8] = [
             (-2, -1), (-2, 1), (-1, -2), (-1, 2),
             (1, -2), (1, 2), (2, -1), (2, 1),
         ];
@@ -563,62 +1387,298 @@ impl Board {
     }
 
     pub fn generate_legal_moves(&self, color: Color) -> Vec<Move> {
-        let pseudo_legal = self.generate_moves(color);
-        pseudo_legal
-            .into_iter()
-            .filter(|m| {
-                let mut clone = self.clone();
-                clone.apply_move_no_check(m);
-                !clone.is_in_check(color)
-            })
-            .collect()
+        let mut legal = Vec::new();
+        self.for_each_legal_move(color, |m| legal.push(m.clone()));
+        legal
     }
 
-    /// Apply a move without checking for game-over conditions (used internally).
-    fn apply_move_no_check(&mut self, m: &Move) {
-        let (fr, fc) = m.from;
-        let (tr, tc) = m.to;
-        self.last_move = Some(((fr, fc), (tr, tc)));
+    /// Call `f` for every legal move of `color` without collecting them.
+    pub fn for_each_legal_move(&self, color: Color, mut f: impl FnMut(&Move)) {
+        for m in &self.generate_moves(color) {
+            // The clone only answers one check query, so skip the attack
+            // map upkeep and scan for attackers directly.
+            let mut clone = self.clone();
+            clone.attacks.take();
+            clone.make_move_on_squares(m);
+            let in_check = clone
+                .find_king(color)
+                .is_some_and(|(kr, kc)| clone.scan_attacked_by(kr, kc, color.opposite()));
+            if !in_check {
+                f(m);
+            }
+        }
+    }
 
-        let piece = match self.squares[fr][fc] {
-            Some(p) => p,
-            None => return,
-        };
+    /// True if `m` captures a piece, including en passant.
+    pub fn is_capture(&self, m: &Move) -> bool {
+        self.squares[m.to.0][m.to.1].is_some() || self.is_en_passant(m)
+    }
 
-        let is_pawn_move = piece.piece_type == PieceType::Pawn;
+    /// True if `m` is a pawn capturing en passant.
+    pub fn is_en_passant(&self, m: &Move) -> bool {
+        Some(m.to) == self.en_passant_target
+            && m.from.1 != m.to.1
+            && self.squares[m.from.0][m.from.1].is_some_and(|p| p.piece_type == PieceType::Pawn)
+    }
 
-        // Record capture
-        if let Some(captured) = self.squares[tr][tc] {
-            match captured.color {
-                Color::White => self.captured_white.push(captured.piece_type),
-                Color::Black => self.captured_black.push(captured.piece_type),
-            }
-        }
+    /// True if `m` is a castle: the king moving two files.
+    pub fn is_castle(&self, m: &Move) -> bool {
+        self.squares[m.from.0][m.from.1].is_some_and(|p| p.piece_type == PieceType::King)
+            && m.from.1.abs_diff(m.to.1) == 2
+    }
 
-        let is_capture = self.squares[tr][tc].is_some();
+    /// True if `m` takes a pawn to its last rank, whether or not
+    /// `m.promotion` says what it becomes.
+    pub fn is_promotion(&self, m: &Move) -> bool {
+        self.squares[m.from.0][m.from.1].is_some_and(|p| {
+            let last_rank = if p.color == Color::White { 7 } else { 0 };
+            p.piece_type == PieceType::Pawn && m.to.0 == last_rank
+        })
+    }
 
-        // En passant capture
-        if is_pawn_move && Some((tr, tc)) == self.en_passant_target {
-            let captured_row = fr;
-            if let Some(ep_piece) = self.squares[captured_row][tc] {
-                match ep_piece.color {
-                    Color::White => self.captured_white.push(ep_piece.piece_type),
-                    Color::Black => self.captured_black.push(ep_piece.piece_type),
-                }
+    /// Legal checks, captures (including en passant) and promotions for
+    /// `color`: the candidate forcing moves in a tactics search.
+    pub fn forcing_moves(&self, color: Color) -> Vec<Move> {
+        let mut forcing = Vec::new();
+        self.for_each_legal_move(color, |m| {
+            let gives_check = || {
+                let mut clone = self.clone();
+                clone.apply_move_no_check(m);
+                clone.is_in_check(color.opposite())
+            };
+            if self.is_capture(m) || m.promotion.is_some() || gives_check() {
+                forcing.push(m.clone());
             }
-            self.squares[captured_row][tc] = None;
-        }
-
-        // Move the piece
-        self.squares[tr][tc] = Some(piece);
-        self.squares[fr][fc] = None;
+        });
+        forcing
+    }
 
-        // Handle promotion
-        if let Some(promo_type) = m.promotion {
-            self.squares[tr][tc] = Some(Piece::new(promo_type, piece.color));
+    /// Call `visit` on every position reachable in exactly `depth` plies (a
+    /// perft walk). Moves are played without game-over detection, so the
+    /// children of a finished game are still visited, as in perft counts.
+    pub fn walk_positions(&self, depth: u32, visit: &mut impl FnMut(&Board)) {
+        if depth == 0 {
+            visit(self);
+            return;
         }
+        self.for_each_legal_move(self.current_turn, |m| {
+            let mut child = self.clone();
+            child.apply_move_no_check(m);
+            child.walk_positions(depth - 1, visit);
+        });
+    }
 
-        // Handle castling (move the rook)
+    /// Number of positions reachable in exactly `depth` plies (perft), for
+    /// verifying move generation against published counts.
+    pub fn perft(&self, depth: u32) -> u64 {
+        match depth {
+            0 => 1,
+            // Bulk count the last ply instead of playing it out.
+            1 => self.legal_move_count(self.current_turn) as u64,
+            _ => {
+                let mut nodes = 0;
+                self.for_each_legal_move(self.current_turn, |m| {
+                    let mut child = self.clone();
+                    child.apply_move_no_check(m);
+                    nodes += child.perft(depth - 1);
+                });
+                nodes
+            }
+        }
+    }
+
+    /// `perft`, with the root moves split across `threads` worker threads.
+    /// Gives the same count as the serial version.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn perft_parallel(&self, depth: u32, threads: usize) -> u64 {
+        if depth <= 1 || threads <= 1 {
+            return self.perft(depth);
+        }
+        let children: Vec<Board> = self
+            .generate_legal_moves(self.current_turn)
+            .iter()
+            .map(|m| {
+                let mut child = self.clone();
+                child.apply_move_no_check(m);
+                child
+            })
+            .collect();
+        let chunk = children.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = children
+                .chunks(chunk)
+                .map(|boards| scope.spawn(move || boards.iter().map(|b| b.perft(depth - 1)).sum::<u64>()))
+                .collect();
+            workers.into_iter().map(|w| w.join().expect("perft worker panicked")).sum()
+        })
+    }
+
+    /// For each square, how many of `color`'s pieces can legally move there
+    /// (a promotion counts once, whatever the piece chosen).
+    pub fn legal_move_targets(&self, color: Color) -> [[u32; 8]; 8] {
+        let mut targets = [[0; 8]; 8];
+        self.for_each_legal_move(color, |m| {
+            // Count each (from, to) pair once: promotions come as four moves
+            if m.promotion.is_none() || m.promotion == Some(PieceType::Queen) {
+                targets[m.to.0][m.to.1] += 1;
+            }
+        });
+        targets
+    }
+
+    /// The side to move's legal move from `from` to `to` with the given
+    /// promotion. A pawn reaching the last rank needs a promotion piece, and
+    /// any other move must not have one; each case gets its own error.
+    pub fn find_legal_move(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        promotion: Option<PieceType>,
+    ) -> Result<Move, ChessError> {
+        let candidates: Vec<Move> = self
+            .generate_legal_moves(self.current_turn)
+            .into_iter()
+            .filter(|m| m.from == from && m.to == to)
+            .collect();
+        if let Some(m) = candidates.iter().find(|m| m.promotion == promotion) {
+            return Ok(m.clone());
+        }
+        match (candidates.first(), promotion) {
+            (None, _) => Err(ChessError::IllegalMove { mv: Move { from, to, promotion }.to_uci() }),
+            (Some(m), None) if self.is_promotion(m) => Err(ChessError::PromotionRequired),
+            (Some(m), Some(_)) if !self.is_promotion(m) => Err(ChessError::NotAPromotion),
+            _ => Err(ChessError::InvalidPromotion { reason: "Invalid promotion piece".to_string() }),
+        }
+    }
+
+    /// True if `m`, promotion included, is a legal move for the side to
+    /// move. Always false once the game is over.
+    pub fn is_legal(&self, m: &Move) -> bool {
+        if self.game_over {
+            return false;
+        }
+        let mut found = false;
+        self.for_each_legal_move(self.current_turn, |l| {
+            found |= l.from == m.from && l.to == m.to && l.promotion == m.promotion;
+        });
+        found
+    }
+
+    /// Number of legal moves for `color`.
+    pub fn legal_move_count(&self, color: Color) -> usize {
+        let mut count = 0;
+        self.for_each_legal_move(color, |_| count += 1);
+        count
+    }
+
+    /// Apply a move without checking for game-over conditions (used internally).
+    fn apply_move_no_check(&mut self, m: &Move) {
+        // Squares whose contents the move changes, then every slider whose
+        // rays pass through them: only those pieces' attacks can change.
+        let mut changed = vec![m.from, m.to];
+        if self.is_en_passant(m) {
+            changed.push((m.from.0, m.to.1));
+        }
+        if self.is_castle(m) {
+            let (rook_from, rook_to) = if m.to.1 > m.from.1 { (7, 5) } else { (0, 3) };
+            changed.extend([(m.from.0, rook_from), (m.from.0, rook_to)]);
+        }
+        let incremental = self.attacks.get().is_some();
+        #[cfg(debug_assertions)]
+        if let Some(map) = self.attacks.get() {
+            self.assert_attack_map_current(map);
+        }
+        let mut sliders = Vec::new();
+        if incremental {
+            for &(r, c) in &changed {
+                self.sliders_reaching(r, c, &mut sliders);
+            }
+            sliders.retain(|sq| !changed.contains(sq));
+            for &sq in changed.iter().chain(&sliders) {
+                self.adjust_attacks(sq, -1);
+            }
+        }
+
+        self.make_move_on_squares(m);
+
+        if incremental {
+            for &sq in changed.iter().chain(&sliders) {
+                self.adjust_attacks(sq, 1);
+            }
+            #[cfg(debug_assertions)]
+            if let Some(map) = self.attacks.get_mut() {
+                map.squares = self.squares;
+            }
+        }
+    }
+
+    /// Add (`sign` = 1) or remove (-1) the attacks of the piece on `sq` in
+    /// the attack map.
+    fn adjust_attacks(&mut self, (row, col): (usize, usize), sign: i8) {
+        let Some(p) = self.squares[row][col] else {
+            return;
+        };
+        // A queen attacks at most 27 squares.
+        let mut targets = [(0, 0); 27];
+        let mut n = 0;
+        self.for_each_attack_from(row, col, |r, c| {
+            targets[n] = (r, c);
+            n += 1;
+        });
+        let Some(map) = self.attacks.get_mut() else {
+            return;
+        };
+        let counts = &mut map.counts[AttackMap::color_index(p.color)];
+        for &(r, c) in &targets[..n] {
+            counts[r][c] = counts[r][c].wrapping_add_signed(sign);
+        }
+    }
+
+    /// The body of `apply_move_no_check`, minus the attack map upkeep.
+    fn make_move_on_squares(&mut self, m: &Move) {
+        let (fr, fc) = m.from;
+        let (tr, tc) = m.to;
+        self.last_move = Some(((fr, fc), (tr, tc)));
+
+        let piece = match self.squares[fr][fc] {
+            Some(p) => p,
+            None => return,
+        };
+
+        let is_pawn_move = piece.piece_type == PieceType::Pawn;
+
+        // Record capture
+        if let Some(captured) = self.squares[tr][tc] {
+            match captured.color {
+                Color::White => self.captured_white.push(captured.piece_type),
+                Color::Black => self.captured_black.push(captured.piece_type),
+            }
+        }
+
+        let is_capture = self.squares[tr][tc].is_some();
+
+        // En passant capture
+        if is_pawn_move && Some((tr, tc)) == self.en_passant_target {
+            let captured_row = fr;
+            if let Some(ep_piece) = self.squares[captured_row][tc] {
+                match ep_piece.color {
+                    Color::White => self.captured_white.push(ep_piece.piece_type),
+                    Color::Black => self.captured_black.push(ep_piece.piece_type),
+                }
+            }
+            self.squares[captured_row][tc] = None;
+        }
+
+        // Move the piece
+        self.squares[tr][tc] = Some(piece);
+        self.squares[fr][fc] = None;
+
+        // Handle promotion
+        if let Some(promo_type) = m.promotion {
+            self.squares[tr][tc] = Some(Piece::new(promo_type, piece.color));
+        }
+
+        // Handle castling (move the rook)
         if piece.piece_type == PieceType::King {
             let col_diff = tc as i32 - fc as i32;
             if col_diff == 2 {
@@ -675,24 +1735,43 @@ impl Board {
         if is_pawn_move || is_capture {
             self.halfmove_clock = 0;
         } else {
-            self.halfmove_clock += 1;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
         }
 
         // Toggle turn
         if self.current_turn == Color::Black {
-            self.fullmove_number += 1;
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
         }
         self.current_turn = self.current_turn.opposite();
         self.position_history.push(self.position_hash());
     }
 
-    /// Apply a move and check for game-over conditions.
+    /// Apply a move and check for game-over conditions. Does nothing once the
+    /// game is over, so a finished game cannot be corrupted.
     pub fn apply_move(&mut self, m: &Move) {
+        if self.game_over {
+            return;
+        }
         self.apply_move_no_check(m);
+        self.update_game_over();
+    }
+
+    /// Set `game_over` and `result` if the position just reached ends the
+    /// game, the way `apply_move` does after every move.
+    fn update_game_over(&mut self) {
+        // King of the Hill: reaching the centre wins outright
+        let mover = self.current_turn.opposite();
+        if self.king_on_hill(mover) {
+            self.game_over = true;
+            self.result = Some(match mover {
+                Color::White => "White wins".to_string(),
+                Color::Black => "Black wins".to_string(),
+            });
+            return;
+        }
 
         // Check for game-over conditions
-        let legal_moves = self.generate_legal_moves(self.current_turn);
-        if legal_moves.is_empty() {
+        if self.legal_move_count(self.current_turn) == 0 {
             self.game_over = true;
             if self.is_in_check(self.current_turn) {
                 // Checkmate
@@ -707,7 +1786,7 @@ impl Board {
         }
 
         // 50-move rule
-        if self.halfmove_clock >= 100 {
+        if self.halfmove_clock >= FIFTY_MOVE_HALFMOVES {
             self.game_over = true;
             self.result = Some("Draw — 50 move rule".to_string());
         }
@@ -724,6 +1803,385 @@ impl Board {
             self.result = Some("Draw — insufficient material".to_string());
         }
     }
+
+    /// Build a position by playing UCI moves from the start position, the way
+    /// `position startpos moves ...` does. Returns `None` if any move is
+    /// malformed or illegal.
+    pub fn from_uci_moves(moves: &[&str]) -> Option<Board> {
+        let moves: Vec<Move> = moves.iter().map(|uci| Move::from_uci(uci)).collect::<Option<_>>()?;
+        Board::from_moves(Variant::Standard, &moves)
+    }
+
+    /// Replay `moves` from the start position of `variant`, as if played
+    /// live, so repetition history and captures are rebuilt. Returns `None`
+    /// if any move is illegal or comes after the game has ended.
+    pub fn from_moves(variant: Variant, moves: &[Move]) -> Option<Board> {
+        let mut board = Board::new();
+        board.variant = variant;
+        (board.apply_moves(moves) == moves.len()).then_some(board)
+    }
+
+    /// Play `moves` in order, e.g. to follow a principal variation, and
+    /// return how many were played: it stops short at the first illegal move
+    /// (which is left unplayed) or once the game is over.
+    pub fn apply_moves(&mut self, moves: &[Move]) -> usize {
+        let mut applied = 0;
+        for m in moves {
+            if !self.is_legal(m) {
+                break;
+            }
+            self.apply_move(m);
+            applied += 1;
+        }
+        applied
+    }
+
+    /// The position in Forsyth–Edwards Notation, e.g.
+    /// "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1". The en
+    /// passant square is given after every double push, as in the FEN spec.
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for row in (0..8).rev() {
+            let mut empty = 0;
+            for col in 0..8 {
+                match self.squares[row][col] {
+                    Some(p) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(p.fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if row > 0 {
+                placement.push('/');
+            }
+        }
+        let turn = if self.current_turn == Color::White { "w" } else { "b" };
+        let castling = self.castling_rights.to_fen();
+        let en_passant = self.en_passant_target.map_or_else(|| "-".to_string(), index_to_square);
+        format!("{placement} {turn} {castling} {en_passant} {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+
+    /// Set up the position described by `fen`, e.g.
+    /// "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1". All six
+    /// fields are read; the move counters may be left off (as in EPD) and
+    /// default to 0 and 1, and must lie within `MAX_FEN_HALFMOVE_CLOCK` and
+    /// 1..=`MAX_FEN_FULLMOVE_NUMBER`. The board has no earlier history, so repetitions
+    /// count from here. Castling rights and en passant squares are taken as
+    /// given; use `validate` to check the position itself. A position that
+    /// ends the game, e.g. checkmate, is loaded with `game_over` set.
+    pub fn from_fen(fen: &str) -> Result<Board, ChessError> {
+        let fen_error = |reason: String| ChessError::InvalidFen { reason };
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if !(4..=6).contains(&fields.len()) {
+            return Err(fen_error(format!("expected 6 fields, found {}", fields.len())));
+        }
+        let mut board = Board::empty();
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(fen_error(format!("expected 8 ranks, found {}", ranks.len())));
+        }
+        for (i, rank) in ranks.iter().enumerate() {
+            let row = 7 - i;
+            let mut col = 0;
+            for c in rank.chars() {
+                if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                    col += skip as usize;
+                } else if let Some(piece) = Piece::from_fen_char(c) {
+                    if col < 8 {
+                        board.squares[row][col] = Some(piece);
+                    }
+                    col += 1;
+                } else {
+                    return Err(fen_error(format!("bad piece character '{c}' on rank {}", row + 1)));
+                }
+                if col > 8 {
+                    return Err(fen_error(format!("rank {} has more than 8 squares", row + 1)));
+                }
+            }
+            if col < 8 {
+                return Err(fen_error(format!("rank {} has only {col} squares", row + 1)));
+            }
+        }
+
+        board.current_turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(fen_error(format!("side to move must be 'w' or 'b', not '{other}'"))),
+        };
+
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let right = match c {
+                    'K' => &mut board.castling_rights.white_kingside,
+                    'Q' => &mut board.castling_rights.white_queenside,
+                    'k' => &mut board.castling_rights.black_kingside,
+                    'q' => &mut board.castling_rights.black_queenside,
+                    _ => return Err(fen_error(format!("bad castling character '{c}'"))),
+                };
+                *right = true;
+            }
+        }
+
+        board.en_passant_target = match fields[3] {
+            "-" => None,
+            square => match square_to_index(square) {
+                Some((row, col)) if row == 2 || row == 5 => Some((row, col)),
+                _ => return Err(fen_error(format!("bad en passant square '{square}'"))),
+            },
+        };
+
+        let counter = |i: usize, name: &str, default: u32, range: std::ops::RangeInclusive<u32>| match fields.get(i) {
+            None => Ok(default),
+            Some(s) => match s.parse::<u32>() {
+                Ok(n) if range.contains(&n) => Ok(n),
+                Ok(_) => Err(fen_error(format!("{name} {s} is outside {}..={}", range.start(), range.end()))),
+                Err(_) => Err(fen_error(format!("bad {name} '{s}'"))),
+            },
+        };
+        board.halfmove_clock = counter(4, "halfmove clock", 0, 0..=MAX_FEN_HALFMOVE_CLOCK)?;
+        board.fullmove_number = counter(5, "fullmove number", 1, 1..=MAX_FEN_FULLMOVE_NUMBER)?;
+
+        board.position_history.push(board.position_hash());
+        // A finished position (mate, or a fifty-move clock already at 100)
+        // loads as a finished game, so it reads the same as one played out.
+        board.update_game_over();
+        Ok(board)
+    }
+
+    /// FEN of the start position of `variant` and after each of `moves`:
+    /// `moves.len() + 1` entries. `None` if the line is illegal (see
+    /// `from_moves`).
+    pub fn fen_history(variant: Variant, moves: &[Move]) -> Option<Vec<String>> {
+        let mut board = Board::new();
+        board.variant = variant;
+        let mut fens = vec![board.to_fen()];
+        for m in moves {
+            if board.game_over {
+                return None;
+            }
+            let legal = board.find_legal_move(m.from, m.to, m.promotion).ok()?;
+            board.apply_move(&legal);
+            fens.push(board.to_fen());
+        }
+        Some(fens)
+    }
+
+    /// Find the legal move described by `san` (e.g. "Nf3", "exd5", "O-O",
+    /// "e8=Q+"). Check and annotation suffixes are optional.
+    pub fn parse_san(&self, san: &str) -> Result<Move, ChessError> {
+        let token = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal = self.generate_legal_moves(self.current_turn);
+
+        let castle_col = match token {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(col) = castle_col {
+            return legal
+                .into_iter()
+                .find(|m| self.is_castle(m) && m.to.1 == col)
+                .ok_or_else(|| ChessError::IllegalMove { mv: san.to_string() });
+        }
+
+        // Promotion suffix: "e8=Q" or "e8Q".
+        let promotion_piece =
+            |piece: &str| PieceType::parse_promotion(piece).map_err(|reason| ChessError::InvalidPromotion { reason });
+        let (body, promotion) = match token.split_once('=') {
+            Some((body, piece)) => (body, Some(promotion_piece(piece)?)),
+            None => match token.char_indices().last() {
+                Some((i, c)) if "QRBN".contains(c) && i >= 2 => (&token[..i], Some(promotion_piece(&token[i..])?)),
+                _ => (token, None),
+            },
+        };
+        if body.len() < 2 || !body.is_char_boundary(body.len() - 2) {
+            return Err(ChessError::ParseError { what: san.to_string() });
+        }
+        let (prefix, dest) = body.split_at(body.len() - 2);
+        let to = square_to_index(dest).ok_or_else(|| ChessError::ParseError { what: san.to_string() })?;
+
+        let mut prefix = prefix.chars().peekable();
+        let piece_type = match prefix.peek() {
+            Some('K') => PieceType::King,
+            Some('Q') => PieceType::Queen,
+            Some('R') => PieceType::Rook,
+            Some('B') => PieceType::Bishop,
+            Some('N') => PieceType::Knight,
+            _ => PieceType::Pawn,
+        };
+        if piece_type != PieceType::Pawn {
+            prefix.next();
+        }
+        // What is left is an optional from-file and/or rank, and an 'x'.
+        let (mut from_col, mut from_row) = (None, None);
+        for c in prefix {
+            match c {
+                'a'..='h' => from_col = Some(c as usize - 'a' as usize),
+                '1'..='8' => from_row = Some(c as usize - '1' as usize),
+                'x' => {}
+                _ => return Err(ChessError::ParseError { what: san.to_string() }),
+            }
+        }
+
+        let mut matches = legal.into_iter().filter(|m| {
+            m.to == to
+                && m.promotion == promotion
+                && self.squares[m.from.0][m.from.1].is_some_and(|p| p.piece_type == piece_type)
+                && from_col.is_none_or(|c| m.from.1 == c)
+                && from_row.is_none_or(|r| m.from.0 == r)
+        });
+        match (matches.next(), matches.next()) {
+            (Some(m), None) => Ok(m),
+            (None, _) => Err(ChessError::IllegalMove { mv: san.to_string() }),
+            (Some(_), Some(_)) => Err(ChessError::AmbiguousSan { san: san.to_string() }),
+        }
+    }
+
+    /// `m` in standard algebraic notation, e.g. "Nbd7", "exd5", "O-O" or
+    /// "e8=Q#". `None` if `m` is not legal here.
+    pub fn move_to_san(&self, m: &Move) -> Option<String> {
+        let legal = self.generate_legal_moves(self.current_turn);
+        legal
+            .iter()
+            .any(|l| l.from == m.from && l.to == m.to && l.promotion == m.promotion)
+            .then(|| self.san_among(m, &legal))
+    }
+
+    /// Every legal move for the side to move in SAN, in generation order.
+    /// Cheaper than `move_to_san` per move, as the legal moves needed for
+    /// disambiguation are generated once.
+    pub fn legal_moves_san(&self) -> Vec<String> {
+        let legal = self.generate_legal_moves(self.current_turn);
+        legal.iter().map(|m| self.san_among(m, &legal)).collect()
+    }
+
+    /// SAN for the legal move `m`, disambiguated against `legal`.
+    fn san_among(&self, m: &Move, legal: &[Move]) -> String {
+        let Some(piece) = self.squares[m.from.0][m.from.1] else {
+            return m.to_uci();
+        };
+        let to = index_to_square(m.to);
+        let from = index_to_square(m.from);
+        let mut san = String::new();
+        if self.is_castle(m) {
+            san.push_str(if m.to.1 == 6 { "O-O" } else { "O-O-O" });
+        } else if piece.piece_type == PieceType::Pawn {
+            if self.is_capture(m) {
+                san.push_str(&from[..1]);
+                san.push('x');
+            }
+            san.push_str(&to);
+            if let Some(pt) = m.promotion {
+                san.push('=');
+                san.push(pt.letter());
+            }
+        } else {
+            san.push(piece.piece_type.letter());
+            // Other pieces of the same kind that can reach the same square:
+            // name our file if that settles it, else our rank, else both.
+            let rivals: Vec<&Move> = legal
+                .iter()
+                .filter(|o| {
+                    o.to == m.to
+                        && o.from != m.from
+                        && self.squares[o.from.0][o.from.1].is_some_and(|p| p.piece_type == piece.piece_type)
+                })
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|o| o.from.1 != m.from.1) {
+                    san.push_str(&from[..1]);
+                } else if rivals.iter().all(|o| o.from.0 != m.from.0) {
+                    san.push_str(&from[1..]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+            if self.is_capture(m) {
+                san.push('x');
+            }
+            san.push_str(&to);
+        }
+
+        let mut after = self.clone_for_search();
+        after.apply_move(m);
+        if after.is_in_check(after.current_turn) {
+            san.push(if after.legal_move_count(after.current_turn) == 0 { '#' } else { '+' });
+        }
+        san
+    }
+
+    /// Play `m` if it is legal and report what happened, so callers need
+    /// not re-inspect the board. An illegal move leaves the board untouched.
+    pub fn play(&mut self, m: &Move) -> PlayResult {
+        if self.game_over {
+            return PlayResult::default();
+        }
+        let Ok(m) = self.find_legal_move(m.from, m.to, m.promotion) else {
+            return PlayResult::default();
+        };
+        let was_capture = self.is_capture(&m);
+        self.apply_move(&m);
+        let result = if self.game_over {
+            self.terminal_reason().map(|reason| GameResult { winner: self.winner(), reason })
+        } else {
+            None
+        };
+        PlayResult {
+            ok: true,
+            game_over: self.game_over,
+            result,
+            was_capture,
+            was_check: self.is_in_check(self.current_turn),
+        }
+    }
+
+    /// Sync primitive for network play: check that this board is the
+    /// position the sender moved from (`expected_prior_hash`, its
+    /// `position_hash`), then play `m` and return the new hash for the next
+    /// exchange. On a desync or an illegal move the board is unchanged.
+    pub fn verify_and_apply(&mut self, m: &Move, expected_prior_hash: u64) -> Result<u64, ChessError> {
+        let hash = self.position_hash();
+        if hash != expected_prior_hash {
+            return Err(ChessError::PositionMismatch { expected: expected_prior_hash, actual: hash });
+        }
+        if self.game_over {
+            return Err(ChessError::GameOver);
+        }
+        let m = self.find_legal_move(m.from, m.to, m.promotion)?;
+        self.apply_move(&m);
+        Ok(self.position_hash())
+    }
+
+    /// Play a move given in SAN. Errors if it is malformed, illegal or
+    /// ambiguous, or the game is over.
+    pub fn apply_san(&mut self, san: &str) -> Result<(), ChessError> {
+        if self.game_over {
+            return Err(ChessError::GameOver);
+        }
+        let m = self.parse_san(san)?;
+        self.apply_move(&m);
+        Ok(())
+    }
+
+    /// Play a move given in UCI notation. Errors if it is malformed or
+    /// illegal, or the game is over.
+    pub fn apply_uci(&mut self, uci: &str) -> Result<(), ChessError> {
+        if self.game_over {
+            return Err(ChessError::GameOver);
+        }
+        let parsed = Move::from_uci(uci).ok_or_else(|| ChessError::ParseError { what: uci.to_string() })?;
+        let m = self.find_legal_move(parsed.from, parsed.to, parsed.promotion)?;
+        self.apply_move(&m);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -761,6 +2219,7 @@ mod tests {
         assert!(!board.game_over);
         board.apply_move(&mv((7, 3), (7, 4))); // Kd8→e8
         assert!(!board.game_over, "game ended after only 2 occurrences — should require 3");
+        assert_eq!(board.repetition_count(), 2);
 
         // Round-trip 2: same moves → 3rd occurrence = draw.
         board.apply_move(&mv((0, 1), (2, 2))); // Nb1→c3
@@ -771,6 +2230,1118 @@ mod tests {
         assert!(!board.game_over);
         board.apply_move(&mv((7, 3), (7, 4))); // Kd8→e8
         assert!(board.game_over, "game should be over after 3 occurrences");
+        assert_eq!(board.repetition_count(), 3);
         assert_eq!(board.result.as_deref(), Some("Draw by repetition"));
     }
+
+    #[test]
+    fn hanging_pieces_uses_static_exchange() {
+        // White Nd4 is attacked by Rd8 and undefended. White Bb5 is attacked
+        // by Rb8 but defended by Pa4 (and Nd4), so RxB PxR loses the exchange.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][3] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[4][1] = Some(Piece::new(PieceType::Bishop, Color::White));
+        board.squares[3][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[7][3] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[7][1] = Some(Piece::new(PieceType::Rook, Color::Black));
+
+        assert_eq!(board.hanging_pieces(Color::White), vec![(3, 3)]);
+        assert_eq!(board.see(3, 3, Color::Black), Some(3.0));
+        assert_eq!(board.see(4, 1, Color::Black), Some(-2.0));
+        assert_eq!(board.attackers_of(4, 1, Color::White), vec![(3, 0), (3, 3)]);
+    }
+
+    #[test]
+    fn validate_reports_each_warning() {
+        assert!(Board::new().validate().is_empty());
+
+        // Two White kings, nine White pawns (e-file open), a Black pawn on h8,
+        // no Black king, and Black to move while Re8 checks Ke1.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[2][3] = Some(Piece::new(PieceType::King, Color::White));
+        for col in [0, 1, 2, 3, 5, 6, 7] {
+            board.squares[1][col] = Some(Piece::new(PieceType::Pawn, Color::White));
+        }
+        board.squares[2][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[2][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[7][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.current_turn = Color::Black;
+
+        let warnings = board.validate();
+        assert_eq!(
+            warnings,
+            vec![
+                PositionWarning::DuplicateKings(Color::White),
+                PositionWarning::TooManyPawns(Color::White),
+                PositionWarning::MissingKing(Color::Black),
+                PositionWarning::PawnOnBackRank((7, 7)),
+                PositionWarning::OpponentInCheck,
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_positions_start_tree_is_consistent() {
+        let mut leaves = 0u64;
+        Board::new().walk_positions(4, &mut |board| {
+            leaves += 1;
+            let (wk, bk) = (board.find_king(Color::White).unwrap(), board.find_king(Color::Black).unwrap());
+            assert!(wk.0.abs_diff(bk.0) > 1 || wk.1.abs_diff(bk.1) > 1, "kings adjacent");
+            assert!(!board.is_in_check(board.current_turn.opposite()), "side that just moved is in check");
+            let counts = board.piece_counts();
+            for color in [Color::White, Color::Black] {
+                assert_eq!(counts.get(color, PieceType::King), 1);
+                assert!(counts.total(color) <= 16);
+            }
+            for col in 0..8 {
+                for row in [0, 7] {
+                    assert!(!matches!(board.squares[row][col], Some(p) if p.piece_type == PieceType::Pawn));
+                }
+            }
+        });
+        assert_eq!(leaves, 197_281, "perft(4) from the start position");
+    }
+
+    #[test]
+    fn parallel_perft_matches_serial() {
+        assert_eq!(Board::new().perft(3), 8_902);
+        let positions = [
+            Board::new(),
+            // Open game: both sides can castle soon.
+            Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"]).unwrap(),
+            // En passant available (e5xd6).
+            Board::from_uci_moves(&["e2e4", "a7a6", "e4e5", "d7d5"]).unwrap(),
+        ];
+        for board in &positions {
+            let serial = board.perft(4);
+            for threads in [3, 8] {
+                assert_eq!(board.perft_parallel(4, threads), serial, "{threads} threads");
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_positions_share_a_book_key() {
+        // White: Kg1, Nf3, pawn e4. Black: Ke8. Black to move.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[2][5] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[3][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.current_turn = Color::Black;
+        board.castling_rights.black_queenside = true;
+
+        // Built independently: Black Kg8, Nf6, pawn e5; White Ke1; White to move.
+        let mut mirrored = Board::empty();
+        mirrored.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        mirrored.squares[5][5] = Some(Piece::new(PieceType::Knight, Color::Black));
+        mirrored.squares[4][4] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        mirrored.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        mirrored.castling_rights.white_queenside = true;
+
+        assert_ne!(board.position_hash(), mirrored.position_hash());
+        assert_eq!(board.color_mirror_hash(), mirrored.position_hash());
+        assert_eq!(mirrored.color_mirror_hash(), board.position_hash());
+        assert_eq!(board.mirror_invariant_hash(), mirrored.mirror_invariant_hash());
+        assert_eq!(board.mirror().mirror().position_hash(), board.position_hash());
+
+        // En passant squares flip with the ranks.
+        let after_e4 = Board::from_uci_moves(&["e2e4"]).unwrap();
+        assert_eq!(after_e4.mirror().en_passant_target, Some((5, 4)));
+    }
+
+    #[test]
+    fn find_legal_move_reports_promotion_errors() {
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+
+        for input in ["n", "knight", "Knight", "N"] {
+            let promo = PieceType::parse_promotion(input).unwrap();
+            let m = board.find_legal_move((6, 0), (7, 0), Some(promo)).unwrap();
+            assert_eq!(m.to_uci(), "a7a8n", "{input}");
+        }
+        assert_eq!(board.find_legal_move((6, 0), (7, 0), None).unwrap_err(), ChessError::PromotionRequired);
+        assert_eq!(
+            board.find_legal_move((6, 0), (7, 0), Some(PieceType::King)).unwrap_err().to_string(),
+            "Invalid promotion piece"
+        );
+        assert_eq!(board.find_legal_move((0, 4), (1, 4), Some(PieceType::Queen)).unwrap_err(), ChessError::NotAPromotion);
+        assert_eq!(
+            board.find_legal_move((0, 4), (3, 4), None).unwrap_err(),
+            ChessError::IllegalMove { mv: "e1e4".to_string() }
+        );
+        assert!(board.find_legal_move((0, 4), (1, 4), None).is_ok());
+    }
+
+    #[test]
+    fn apply_san_plays_a_short_game() {
+        let mut board = Board::new();
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O", "Bc5", "d3", "d6", "Bg5", "h6", "Bxf6", "Qxf6"] {
+            board.apply_san(san).unwrap_or_else(|e| panic!("{san}: {e}"));
+        }
+        let uci = [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f8c5", "d2d3", "d7d6", "c1g5", "h7h6", "g5f6", "d8f6",
+        ];
+        let expected = Board::from_uci_moves(&uci).unwrap();
+        assert_eq!(board.position_hash(), expected.position_hash());
+        assert_eq!(board.squares, expected.squares);
+        assert!(board.apply_san("Qxf7#").is_err(), "nothing can reach f7 with mate");
+    }
+
+    #[test]
+    fn apply_san_handles_suffixes_promotion_and_ambiguity() {
+        let mut board = Board::empty();
+        board.squares[1][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[0][7] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.position_history.push(board.position_hash());
+
+        assert_eq!(board.parse_san("Rd1").unwrap_err(), ChessError::AmbiguousSan { san: "Rd1".to_string() });
+        assert_eq!(board.parse_san("Rad1").unwrap().to_uci(), "a1d1");
+        assert_eq!(board.parse_san("Rhf1").unwrap().to_uci(), "h1f1");
+        assert_eq!(board.parse_san("a8=N").unwrap().to_uci(), "a7a8n");
+        assert_eq!(board.parse_san("a8Q+").unwrap().to_uci(), "a7a8q");
+        assert!(board.parse_san("a8").is_err(), "promotion piece is required");
+        assert_eq!(board.parse_san("Kz9").unwrap_err(), ChessError::ParseError { what: "Kz9".to_string() });
+        assert!(matches!(board.parse_san("a8=K"), Err(ChessError::InvalidPromotion { .. })));
+        assert_eq!(board.parse_san("Nc3").unwrap_err(), ChessError::IllegalMove { mv: "Nc3".to_string() });
+
+        board.apply_san("a8=Q+").unwrap();
+        assert_eq!(board.squares[7][0], Some(Piece::new(PieceType::Queen, Color::White)));
+        assert!(board.is_in_check(Color::Black));
+    }
+
+    #[test]
+    fn play_reports_checkmate() {
+        let mut board = Board::from_uci_moves(&["f2f3", "e7e5", "g2g4"]).unwrap();
+        let before = board.position_hash();
+        let illegal = board.play(&Move::from_uci("d8d1").unwrap());
+        assert_eq!(illegal, PlayResult::default());
+        assert_eq!(board.position_hash(), before, "illegal move must not change the board");
+
+        let quiet = board.play(&Move::from_uci("b8c6").unwrap());
+        assert!(quiet.ok && !quiet.game_over && !quiet.was_capture && !quiet.was_check);
+        board.play(&Move::from_uci("a2a3").unwrap());
+
+        let mate = board.play(&Move::from_uci("d8h4").unwrap());
+        assert!(mate.ok && mate.game_over && mate.was_check && !mate.was_capture);
+        assert_eq!(mate.result, Some(GameResult { winner: Some(Color::Black), reason: TerminalReason::Checkmate }));
+        assert!(!board.play(&Move::from_uci("a3a4").unwrap()).ok, "game is over");
+    }
+
+    #[test]
+    fn incremental_attack_map_matches_recomputation() {
+        let mut seed = 0x5eed_u64;
+        for game in 0..20 {
+            let mut board = Board::new();
+            board.attack_map();
+            for ply in 0..80 {
+                let moves = board.generate_legal_moves(board.current_turn);
+                if moves.is_empty() || board.game_over {
+                    break;
+                }
+                let (next, r) = splitmix64(seed);
+                seed = next;
+                board.apply_move(&moves[(r % moves.len() as u64) as usize]);
+
+                let map = board.attacks.get().expect("apply_move keeps a built map current");
+                let fresh = AttackMap::compute(&board);
+                for row in 0..8 {
+                    for col in 0..8 {
+                        for color in [Color::White, Color::Black] {
+                            assert_eq!(
+                                map.count(row, col, color),
+                                fresh.count(row, col, color),
+                                "game {game} ply {ply}, square ({row}, {col}), {color:?}"
+                            );
+                            assert_eq!(
+                                board.is_square_attacked_by(row, col, color),
+                                board.scan_attacked_by(row, col, color)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_square_rebuilds_the_attack_map_on_the_next_query() {
+        let mut board = Board::new();
+        assert!(!board.is_square_attacked_by(4, 4, Color::White));
+        board.set_square(3, 3, Some(Piece::new(PieceType::Pawn, Color::White)));
+        assert!(board.attacks.get().is_none());
+        assert!(board.is_square_attacked_by(4, 4, Color::White));
+        assert!(board.attacks.get().is_some());
+        assert_eq!(board.attack_count(2, 2, Color::White), 3, "b1 knight and b2, d2 pawns hit c3");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "use `set_square`")]
+    fn direct_square_write_after_an_attack_query_is_caught() {
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        assert!(!board.is_in_check(Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.is_in_check(Color::White);
+    }
+
+    #[test]
+    fn detects_knight_fork() {
+        // Nc7+ forks the e8 king and the a8 rook.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[6][2] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[7][0] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        assert_eq!(
+            board.tactical_motifs(Color::White),
+            vec![Motif::Fork { attacker: (6, 2), targets: vec![(7, 0), (7, 4)] }]
+        );
+        assert!(board.tactical_motifs(Color::Black).is_empty());
+
+        // A defended pawn is no fork target: Nd5 eyes c7 and e7.
+        let mut quiet = Board::empty();
+        quiet.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        quiet.squares[4][3] = Some(Piece::new(PieceType::Knight, Color::White));
+        quiet.squares[7][3] = Some(Piece::new(PieceType::King, Color::Black));
+        quiet.squares[6][2] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        quiet.squares[6][4] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        assert!(quiet.tactical_motifs(Color::White).is_empty());
+    }
+
+    #[test]
+    fn detects_bishop_skewer_and_pin() {
+        // Ba4+ skewers the c6 king to the e8 queen.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][0] = Some(Piece::new(PieceType::Bishop, Color::White));
+        board.squares[5][2] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[7][4] = Some(Piece::new(PieceType::Queen, Color::Black));
+        assert_eq!(
+            board.tactical_motifs(Color::White),
+            vec![Motif::Skewer { attacker: (3, 0), front: (5, 2), behind: (7, 4) }]
+        );
+
+        // Swap them and it is a pin: the queen on c6 can't leave the king.
+        board.squares[5][2] = Some(Piece::new(PieceType::Queen, Color::Black));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        assert_eq!(
+            board.tactical_motifs(Color::White),
+            vec![Motif::Pin { attacker: (3, 0), pinned: (5, 2), behind: (7, 4) }]
+        );
+        // A friendly piece in between breaks the line.
+        board.squares[4][1] = Some(Piece::new(PieceType::Pawn, Color::White));
+        assert!(board.tactical_motifs(Color::White).is_empty());
+    }
+
+    #[test]
+    fn warns_of_back_rank_mate_threat() {
+        // White to move with a boxed-in king: Black threatens Re1#.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[1][5] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[3][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][5] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[4][4] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert!(board.opponent_has_mate_in_one());
+        assert_eq!(board.null_move().find_mate(1).unwrap().to_uci(), "e5e1");
+        // White's own Ra8+ is only check: the e-rook can interpose.
+        assert!(board.find_mate(1).is_none());
+
+        // With luft on h3 the king escapes, so there is no threat.
+        board.set_square(1, 7, None);
+        board.set_square(2, 7, Some(Piece::new(PieceType::Pawn, Color::White)));
+        assert!(!board.opponent_has_mate_in_one());
+    }
+
+    #[test]
+    fn material_signature_of_classic_endings() {
+        let setup = |white: &[(PieceType, (usize, usize))], black: &[(PieceType, (usize, usize))]| {
+            let mut board = Board::empty();
+            for &(pt, (r, c)) in white {
+                board.squares[r][c] = Some(Piece::new(pt, Color::White));
+            }
+            for &(pt, (r, c)) in black {
+                board.squares[r][c] = Some(Piece::new(pt, Color::Black));
+            }
+            board.material_signature()
+        };
+        use PieceType::*;
+        assert_eq!(setup(&[(King, (0, 4)), (Queen, (0, 3))], &[(King, (7, 4))]), "KQvK");
+        // The stronger side comes first whichever colour it is.
+        assert_eq!(setup(&[(King, (0, 4))], &[(King, (7, 4)), (Rook, (7, 0))]), "KRvK");
+        assert_eq!(setup(&[(King, (0, 4)), (Knight, (0, 1)), (Bishop, (0, 2))], &[(King, (7, 4))]), "KBNvK");
+        assert_eq!(setup(&[(King, (0, 4))], &[(King, (7, 4)), (Pawn, (3, 3))]), "KPvK");
+        assert_eq!(
+            setup(&[(King, (0, 4)), (Rook, (0, 0))], &[(King, (7, 4)), (Rook, (7, 0)), (Pawn, (6, 0))]),
+            "KRPvKR"
+        );
+        // Equal material: the longer code leads, independent of colour.
+        let pawns = [(King, (7, 4)), (Pawn, (6, 0)), (Pawn, (6, 1)), (Pawn, (6, 2))];
+        assert_eq!(setup(&[(King, (0, 4)), (Bishop, (0, 2))], &pawns), "KPPPvKB");
+        assert_eq!(setup(&[(King, (0, 4)), (Rook, (0, 0))], &[(King, (7, 4)), (Rook, (7, 0))]), "KRvKR");
+        assert_eq!(Board::new().material_signature(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+    }
+
+    #[test]
+    fn legal_moves_san_disambiguates_knights() {
+        // Knights on b1 and f1 (and a rook pair on a3/a5) share targets.
+        let mut board = Board::empty();
+        board.squares[0][7] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][1] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[0][5] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[2][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[4][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[1][3] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        let san = board.legal_moves_san();
+        for expected in ["Nbxd2", "Nfxd2", "Nc3", "Ng3", "R3a4", "R5a4", "Re5+", "Ra8+"] {
+            assert!(san.contains(&expected.to_string()), "missing {expected} in {san:?}");
+        }
+        assert!(!san.iter().any(|s| s == "Nxd2" || s == "Ra4"));
+        assert_eq!(san.len(), board.legal_move_count(Color::White));
+        // Every SAN string parses back to a distinct legal move.
+        for s in &san {
+            let m = board.parse_san(s).unwrap();
+            assert_eq!(board.move_to_san(&m).as_deref(), Some(s.as_str()));
+        }
+        assert!(board.move_to_san(&mv((0, 1), (3, 3))).is_none());
+    }
+
+    #[test]
+    fn san_for_pawns_castles_and_mate() {
+        let board = Board::from_uci_moves(&["e2e4", "d7d5"]).unwrap();
+        assert_eq!(board.move_to_san(&mv((3, 4), (4, 3))).as_deref(), Some("exd5"));
+        let mate = Board::from_uci_moves(&["f2f3", "e7e5", "g2g4"]).unwrap();
+        assert_eq!(mate.move_to_san(&mv((7, 3), (3, 7))).as_deref(), Some("Qh4#"));
+        let castle = castling_board();
+        assert_eq!(castle.move_to_san(&mv((0, 4), (0, 6))).as_deref(), Some("O-O"));
+        assert_eq!(castle.move_to_san(&mv((0, 4), (0, 2))).as_deref(), Some("O-O-O"));
+        let mut promo = Board::empty();
+        promo.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        promo.squares[6][1] = Some(Piece::new(PieceType::Pawn, Color::White));
+        promo.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        let m = Move { from: (6, 1), to: (7, 1), promotion: Some(PieceType::Queen) };
+        assert_eq!(promo.move_to_san(&m).as_deref(), Some("b8=Q+"));
+    }
+
+    /// White pawns on e7 (to promote quietly on e8 or by taking the d8
+    /// queen) and g7 (to take the h8 rook), kings out of the way on a1 and
+    /// a7, and the halfmove clock already running.
+    fn promotion_board() -> Board {
+        let mut board = Board::empty();
+        board.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[6][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[6][0] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[7][3] = Some(Piece::new(PieceType::Queen, Color::Black));
+        board.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.castling_rights.black_kingside = true;
+        board.halfmove_clock = 17;
+        board
+    }
+
+    #[test]
+    fn quiet_promotion_resets_the_clock() {
+        let mut board = promotion_board();
+        let m = Move { from: (6, 4), to: (7, 4), promotion: Some(PieceType::Knight) };
+        assert!(board.play(&m).ok);
+        assert_eq!(board.halfmove_clock, 0);
+        assert_eq!(board.squares[7][4], Some(Piece::new(PieceType::Knight, Color::White)));
+        assert!(board.squares[6][4].is_none());
+        assert!(board.captured_black.is_empty());
+    }
+
+    #[test]
+    fn capturing_promotion_resets_the_clock_and_records_the_capture() {
+        let mut board = promotion_board();
+        let m = Move { from: (6, 4), to: (7, 3), promotion: Some(PieceType::Rook) };
+        assert!(board.play(&m).was_capture);
+        assert_eq!(board.halfmove_clock, 0);
+        assert_eq!(board.squares[7][3], Some(Piece::new(PieceType::Rook, Color::White)));
+        assert_eq!(board.captured_black, vec![PieceType::Queen]);
+        assert!(board.captured_white.is_empty());
+        // The clock runs again from zero on the next reversible move.
+        board.apply_move(&mv((6, 0), (5, 0)));
+        assert_eq!(board.halfmove_clock, 1);
+
+        // Promoting onto the h8 rook also ends black's kingside castling.
+        let mut board = promotion_board();
+        let m = Move { from: (6, 6), to: (7, 7), promotion: Some(PieceType::Queen) };
+        board.apply_move(&m);
+        assert_eq!(board.squares[7][7], Some(Piece::new(PieceType::Queen, Color::White)));
+        assert_eq!(board.captured_black, vec![PieceType::Rook]);
+        assert!(!board.castling_rights.black_kingside);
+        assert_eq!(board.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn endgame_by_phase() {
+        assert!(!Board::new().is_endgame());
+        let mut krk = Board::empty();
+        krk.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        krk.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        krk.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        assert!(krk.is_endgame());
+        // Queens alone (4 + 4 of 24) are a middlegame.
+        krk.squares[0][3] = Some(Piece::new(PieceType::Queen, Color::White));
+        krk.squares[7][3] = Some(Piece::new(PieceType::Queen, Color::Black));
+        assert!(!krk.is_endgame());
+    }
+
+    #[test]
+    fn from_fen_round_trips() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 40",
+            "8/8/8/3k4/8/8/8/4K2R b - - 99 120",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(board.to_fen(), fen);
+            assert_eq!(board.position_history, vec![board.position_hash()]);
+        }
+        let start = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(start.position_hash(), Board::new().position_hash());
+        assert_eq!(start.legal_move_count(Color::White), 20);
+        // Every position along a game survives the trip.
+        let mut game = Board::new();
+        for uci in ["e2e4", "c7c5", "e4e5", "d7d5", "g1f3", "b8c6", "f1b5", "g8f6", "e1g1"] {
+            game.apply_uci(uci).unwrap();
+            let loaded = Board::from_fen(&game.to_fen()).unwrap();
+            assert_eq!(loaded.to_fen(), game.to_fen());
+            assert_eq!(loaded.position_hash(), game.position_hash());
+            assert_eq!(loaded.generate_legal_moves(loaded.current_turn).len(), game.legal_move_count(game.current_turn));
+        }
+        // Move counters are optional.
+        assert_eq!(Board::from_fen("8/8/8/8/8/8/8/K6k w - -").unwrap().to_fen(), "8/8/8/8/8/8/8/K6k w - - 0 1");
+    }
+
+    #[test]
+    fn from_fen_rejects_malformed_input() {
+        for (fen, reason) in [
+            ("", "expected 6 fields, found 0"),
+            ("8/8/8/8/8/8/8 w - - 0 1", "expected 8 ranks, found 7"),
+            ("8/8/8/8/8/8/8/K6x w - - 0 1", "bad piece character 'x' on rank 1"),
+            ("8/8/8/8/8/8/8/K7k w - - 0 1", "rank 1 has more than 8 squares"),
+            ("8/8/8/8/8/8/8/K5k w - - 0 1", "rank 1 has only 7 squares"),
+            ("8/8/8/8/8/8/8/K6k x - - 0 1", "side to move must be 'w' or 'b', not 'x'"),
+            ("8/8/8/8/8/8/8/K6k w KX - 0 1", "bad castling character 'X'"),
+            ("8/8/8/8/8/8/8/K6k w - e4 0 1", "bad en passant square 'e4'"),
+            ("8/8/8/8/8/8/8/K6k w - i3 0 1", "bad en passant square 'i3'"),
+            ("8/8/8/8/8/8/8/K6k w - - x 1", "bad halfmove clock 'x'"),
+            ("8/8/8/8/8/8/8/K6k w - - 4000000000 1", "halfmove clock 4000000000 is outside 0..=150"),
+            ("8/8/8/8/8/8/8/K6k w - - 0 0", "fullmove number 0 is outside 1..=10000"),
+            ("8/8/8/8/8/8/8/K6k w - - 0 4294967295", "fullmove number 4294967295 is outside 1..=10000"),
+            ("8/8/8/8/8/8/8/K6k w - - 0 99999999999", "bad fullmove number '99999999999'"),
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap_err(), ChessError::InvalidFen { reason: reason.to_string() }, "{fen:?}");
+        }
+    }
+
+    #[test]
+    fn apply_moves_stops_at_mate_and_illegal_moves() {
+        // Fool's mate, with a PV that runs on past the mate.
+        let pv: Vec<Move> = ["f2f3", "e7e5", "g2g4", "d8h4", "e1f2", "h4f2"]
+            .iter()
+            .map(|m| Move::from_uci(m).unwrap())
+            .collect();
+        let mut board = Board::new();
+        assert_eq!(board.apply_moves(&pv), 4);
+        assert_eq!(board.winner(), Some(Color::Black));
+        assert_eq!(board.apply_moves(&pv[4..]), 0);
+
+        let mut board = Board::new();
+        let line = [mv((1, 4), (3, 4)), mv((6, 4), (4, 4)), mv((3, 4), (4, 4)), mv((7, 6), (5, 5))];
+        assert_eq!(board.apply_moves(&line), 2, "e4xe5 is not a legal pawn move");
+        assert_eq!(board.to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+    }
+
+    #[test]
+    fn castling_rights_as_fen_clear_after_king_moves() {
+        let mut board = Board::new();
+        assert_eq!(board.castling_rights.to_fen(), "KQkq");
+        board.apply_uci("e2e4").unwrap();
+        board.apply_uci("h7h5").unwrap();
+        board.apply_uci("e1e2").unwrap();
+        assert_eq!(board.castling_rights.to_fen(), "kq");
+        board.apply_uci("h8h6").unwrap();
+        assert_eq!(board.castling_rights.to_fen(), "q");
+        for uci in ["e2e1", "e7e6", "e1e2", "e8e7"] {
+            board.apply_uci(uci).unwrap();
+        }
+        assert_eq!(board.castling_rights.to_fen(), "-");
+    }
+
+    #[test]
+    fn detects_promotion_without_a_piece() {
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+
+        assert!(board.is_promotion(&Move::from_uci("h7h8").unwrap()));
+        assert!(board.is_promotion(&Move::from_uci("h7h8n").unwrap()));
+        assert!(board.is_promotion(&Move::from_uci("g2g1").unwrap()));
+        assert!(!board.is_promotion(&Move::from_uci("e1e2").unwrap()));
+        assert!(!board.is_promotion(&Move::from_uci("h7h6").unwrap()), "not the last rank");
+        assert_eq!(board.find_legal_move((6, 7), (7, 7), None).unwrap_err(), ChessError::PromotionRequired);
+    }
+
+    #[test]
+    fn ply_from_move_number_and_turn() {
+        assert_eq!(Board::new().ply(), 0);
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(board.ply(), 3);
+        assert_eq!(board.ply() as usize, board.position_history.len() - 1);
+
+        // Move 10, Black to move, as a FEN ending "b - - 0 10" would set up.
+        let mut set_up = Board::empty();
+        set_up.fullmove_number = 10;
+        set_up.current_turn = Color::Black;
+        assert_eq!(set_up.ply(), 19);
+    }
+
+    #[test]
+    fn pawn_attacks_square_both_colours_and_edges() {
+        let mut board = Board::empty();
+        board.squares[1][0] = Some(Piece::new(PieceType::Pawn, Color::White)); // a2
+        board.squares[3][4] = Some(Piece::new(PieceType::Pawn, Color::White)); // e4
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black)); // h7
+        board.squares[4][3] = Some(Piece::new(PieceType::Pawn, Color::Black)); // d5
+
+        // a2 only reaches b3; h7 only reaches g6.
+        assert!(board.pawn_attacks_square(2, 1, Color::White));
+        assert!(!board.pawn_attacks_square(2, 0, Color::White));
+        assert!(board.pawn_attacks_square(5, 6, Color::Black));
+        assert!(!board.pawn_attacks_square(5, 7, Color::Black));
+        // e4 and d5 attack each other; neither attacks straight ahead.
+        assert!(board.pawn_attacks_square(4, 3, Color::White));
+        assert!(board.pawn_attacks_square(4, 5, Color::White));
+        assert!(board.pawn_attacks_square(3, 4, Color::Black));
+        assert!(board.pawn_attacks_square(3, 2, Color::Black));
+        assert!(!board.pawn_attacks_square(4, 4, Color::White));
+        assert!(!board.pawn_attacks_square(3, 3, Color::Black));
+        // Colours don't mix, and the back ranks can't be pawn-attacked from behind.
+        assert!(!board.pawn_attacks_square(4, 3, Color::Black));
+        assert!(!board.pawn_attacks_square(0, 1, Color::White));
+        assert!(!board.pawn_attacks_square(7, 6, Color::Black));
+    }
+
+    #[test]
+    fn is_legal_checks_side_to_move_and_promotion() {
+        let board = Board::new();
+        let legal = |b: &Board, uci: &str| Move::from_uci(uci).is_some_and(|m| b.is_legal(&m));
+        assert!(legal(&board, "e2e4"));
+        assert!(legal(&board, "g1f3"));
+        assert!(!legal(&board, "e2e5"));
+        assert!(!legal(&board, "e7e5"), "not Black's turn");
+        assert!(!legal(&board, "e2"));
+        assert!(!legal(&board, "z9e4"));
+
+        let mut promo = Board::empty();
+        promo.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        promo.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black));
+        promo.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        assert!(legal(&promo, "h7h8q"));
+        assert!(!legal(&promo, "h7h8"), "promotion piece required");
+    }
+
+    #[test]
+    fn search_clone_keeps_repetitions() {
+        // Knights out and back after 1.e3 e6: the start of the cycle recurs,
+        // the positions before the pawn moves cannot.
+        let line = ["e2e3", "e7e6", "g1f3", "g8f6", "f3g1", "f6g8"];
+        let board = Board::from_uci_moves(&line).unwrap();
+        let trimmed = board.clone_for_search();
+        assert_eq!(trimmed.position_history.len(), 5);
+        assert!(trimmed.position_history.len() < board.position_history.len());
+        assert_eq!(trimmed.repetition_count(), 2);
+        assert_eq!(trimmed.repetition_count(), board.repetition_count());
+        assert_eq!(trimmed.position_hash(), board.position_hash());
+
+        // One more knight move repeats a position inside the search too.
+        let mut child = trimmed.clone();
+        child.apply_move(&Move::from_uci("g1f3").unwrap());
+        assert!(child.is_repetition());
+    }
+
+    #[test]
+    fn fen_of_start_and_after_moves() {
+        assert_eq!(Board::new().to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let board = Board::from_uci_moves(&["e2e4", "c7c5", "g1f3"]).unwrap();
+        assert_eq!(board.to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        let moves: Vec<Move> = ["e2e4", "e7e5", "e1e2"].iter().map(|m| Move::from_uci(m).unwrap()).collect();
+        let fens = Board::fen_history(Variant::Standard, &moves).unwrap();
+        assert_eq!(fens.len(), moves.len() + 1);
+        assert_eq!(fens[0], Board::new().to_fen());
+        assert_eq!(fens[1], "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        assert_eq!(fens.last().unwrap(), &Board::from_moves(Variant::Standard, &moves).unwrap().to_fen());
+        assert!(fens[3].contains(" b kq "), "king move drops White's rights: {}", fens[3]);
+    }
+
+    #[test]
+    fn verify_and_apply_detects_desync() {
+        let mut sender = Board::new();
+        let mut peer = Board::new();
+        let e4 = Move::from_uci("e2e4").unwrap();
+
+        let prior = sender.position_hash();
+        sender.apply_move(&e4);
+        assert_eq!(peer.verify_and_apply(&e4, prior), Ok(sender.position_hash()));
+
+        // A stale hash (the peer is a move ahead) is rejected untouched.
+        let before = peer.position_hash();
+        assert!(matches!(
+            peer.verify_and_apply(&Move::from_uci("e7e5").unwrap(), prior),
+            Err(ChessError::PositionMismatch { .. })
+        ));
+        assert_eq!(peer.position_hash(), before);
+        assert_eq!(
+            peer.verify_and_apply(&Move::from_uci("e7e4").unwrap(), before).unwrap_err(),
+            ChessError::IllegalMove { mv: "e7e4".to_string() }
+        );
+    }
+
+    #[test]
+    fn legal_move_targets_at_start() {
+        let targets = Board::new().legal_move_targets(Color::White);
+        // Rank 3: every pawn, plus the knights on a3, c3, f3 and h3.
+        assert_eq!(targets[2], [2, 1, 2, 1, 1, 2, 1, 2]);
+        // Rank 4: double pawn pushes only.
+        assert_eq!(targets[3], [1; 8]);
+        let total: u32 = targets.iter().flatten().sum();
+        assert_eq!(total, 20);
+    }
+
+    #[test]
+    fn classifies_captures_and_castles() {
+        // 1.e4 d5: exd5 is a normal capture. After 2.e5 f5, exf6 is en
+        // passant onto an empty square.
+        let mut board = Board::from_uci_moves(&["e2e4", "d7d5"]).unwrap();
+        assert!(board.is_capture(&Move::from_uci("e4d5").unwrap()));
+        assert!(!board.is_capture(&Move::from_uci("e4e5").unwrap()));
+        for uci in ["e4e5", "f7f5"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+        let ep = Move::from_uci("e5f6").unwrap();
+        assert!(board.squares[5][5].is_none());
+        assert!(board.is_capture(&ep) && board.is_en_passant(&ep));
+        assert!(!board.is_castle(&ep));
+
+        let board = castling_board();
+        assert!(board.is_castle(&Move::from_uci("e1g1").unwrap()));
+        assert!(board.is_castle(&Move::from_uci("e1c1").unwrap()));
+        assert!(!board.is_castle(&Move::from_uci("e1f1").unwrap()));
+        assert!(!board.is_castle(&Move::from_uci("a1c1").unwrap()));
+    }
+
+    #[test]
+    fn forcing_moves_are_checks_captures_and_promotions() {
+        // White Ka1, Ra2, Pd4; Black Kh8, Ph7, Pc5, Pe5.
+        // Forcing: Ra8+ and dxc5, dxe5.
+        let mut board = Board::empty();
+        board.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[1][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[3][3] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[4][2] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[4][4] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut forcing: Vec<String> = board.forcing_moves(Color::White).iter().map(|m| m.to_uci()).collect();
+        forcing.sort();
+        assert_eq!(forcing, ["a2a8", "d4c5", "d4e5"]);
+    }
+
+    #[test]
+    fn apply_move_after_checkmate_is_ignored() {
+        // Fool's mate: 1.f3 e5 2.g4 Qh4#
+        let mut board = Board::from_uci_moves(&["f2f3", "e7e5", "g2g4", "d8h4"]).unwrap();
+        assert!(board.game_over);
+        let squares = board.squares;
+        let history_len = board.position_history.len();
+
+        board.apply_move(&Move::from_uci("a2a3").unwrap());
+        assert_eq!(board.squares, squares);
+        assert_eq!(board.position_history.len(), history_len);
+        assert_eq!(board.current_turn, Color::White);
+        assert_eq!(board.result.as_deref(), Some("Black wins"));
+    }
+
+    #[test]
+    fn near_max_counters_saturate() {
+        let mut board = Board::new();
+        board.current_turn = Color::Black;
+        board.fullmove_number = u32::MAX;
+        board.halfmove_clock = u32::MAX;
+        board.apply_move(&Move::from_uci("g8f6").unwrap());
+        assert_eq!(board.fullmove_number, u32::MAX);
+        assert_eq!(board.halfmove_clock, u32::MAX);
+        assert_eq!(board.moves_until_fifty_move_draw(), 0);
+    }
+
+    #[test]
+    fn fen_with_near_max_counters_loads_and_plays() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 b - - 98 10000").unwrap();
+        assert_eq!((board.halfmove_clock, board.fullmove_number), (98, MAX_FEN_FULLMOVE_NUMBER));
+        board.apply_uci("e8d8").unwrap();
+        assert_eq!(board.fullmove_number, MAX_FEN_FULLMOVE_NUMBER + 1);
+        assert!(!board.game_over);
+        board.apply_uci("a1a2").unwrap();
+        assert!(board.game_over, "the fifty-move rule still ends the game");
+        assert!(board.to_fen().ends_with(" 100 10001"));
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 150 1").unwrap();
+        assert!(board.game_over);
+    }
+
+    #[test]
+    fn legal_move_count_matches_generated_moves() {
+        let mut board = Board::new();
+        assert_eq!(board.legal_move_count(Color::White), 20);
+        for uci in ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6"] {
+            let m = Move::from_uci(uci).unwrap();
+            board.apply_move(&m);
+            for color in [Color::White, Color::Black] {
+                assert_eq!(board.legal_move_count(color), board.generate_legal_moves(color).len());
+            }
+        }
+    }
+
+    #[test]
+    fn from_moves_rebuilds_an_intermediate_position() {
+        let line: Vec<Move> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6"]
+            .iter()
+            .map(|uci| Move::from_uci(uci).unwrap())
+            .collect();
+        let mut live = Board::new();
+        let mut at_ply_3 = None;
+        for (ply, m) in line.iter().enumerate() {
+            live.apply_move(m);
+            if ply == 2 {
+                at_ply_3 = Some(live.clone());
+            }
+        }
+        let at_ply_3 = at_ply_3.unwrap();
+
+        let replayed = Board::from_moves(Variant::Standard, &line[..3]).unwrap();
+        assert_eq!(replayed.squares, at_ply_3.squares);
+        assert_eq!(replayed.current_turn, Color::Black);
+        assert_eq!(replayed.position_history, at_ply_3.position_history);
+        assert_eq!(replayed.position_history.len(), 4);
+        assert_eq!(replayed.position_hash(), at_ply_3.position_hash());
+    }
+
+    #[test]
+    fn from_uci_moves_plays_from_start() {
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(board.current_turn, Color::Black);
+        assert_eq!(board.squares[2][5], Some(Piece::new(PieceType::Knight, Color::White)));
+        assert_eq!(board.squares[4][4], Some(Piece::new(PieceType::Pawn, Color::Black)));
+        assert!(Board::from_uci_moves(&["e2e5"]).is_none());
+        assert!(Board::from_uci_moves(&["zz"]).is_none());
+    }
+
+    #[test]
+    fn king_of_the_hill_king_on_e4_wins() {
+        let mut board = Board::empty();
+        board.variant = Variant::KingOfTheHill;
+        board.squares[2][4] = Some(Piece::new(PieceType::King, Color::White)); // Ke3
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black)); // Ke8
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.position_history.push(board.position_hash());
+
+        let mut standard = board.clone();
+        standard.variant = Variant::Standard;
+        standard.apply_move(&mv((2, 4), (3, 4)));
+        assert!(!standard.game_over, "reaching e4 means nothing in standard chess");
+
+        board.apply_move(&mv((2, 4), (3, 4))); // Ke3→e4
+        assert!(board.game_over);
+        assert_eq!(board.result.as_deref(), Some("White wins"));
+        assert_eq!(board.winner(), Some(Color::White));
+        assert_eq!(board.terminal_reason(), Some(TerminalReason::KingOfTheHill));
+    }
+
+    #[test]
+    fn king_of_the_hill_bare_kings_are_not_drawn() {
+        let mut board = Board::empty();
+        board.variant = Variant::KingOfTheHill;
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White)); // Ke1
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black)); // Ke8
+        board.squares[1][0] = Some(Piece::new(PieceType::Knight, Color::White)); // a2
+        board.position_history.push(board.position_hash());
+
+        let mut standard = board.clone();
+        standard.variant = Variant::Standard;
+        standard.apply_move(&mv((1, 0), (3, 1))); // Na2→b4
+        assert!(standard.game_over, "K+N v K is a dead draw in standard chess");
+
+        board.apply_move(&mv((1, 0), (3, 1)));
+        assert!(!board.game_over);
+        assert_eq!(board.terminal_reason(), None);
+        board.squares[3][1] = None;
+        assert!(!board.has_insufficient_material(), "bare kings can still race to the centre");
+    }
+
+    #[test]
+    fn fifty_move_countdown_tracks_reversible_moves() {
+        let mut board = Board::new();
+        assert_eq!(board.moves_until_fifty_move_draw(), 100);
+        board.apply_move(&mv((0, 6), (2, 5))); // Ng1→f3
+        board.apply_move(&mv((7, 6), (5, 5))); // Ng8→f6
+        board.apply_move(&mv((2, 5), (0, 6))); // Nf3→g1
+        assert_eq!(board.moves_until_fifty_move_draw(), 97);
+        board.apply_move(&mv((6, 4), (4, 4))); // e7→e5 resets the clock
+        assert_eq!(board.moves_until_fifty_move_draw(), 100);
+
+        board.halfmove_clock = 120;
+        assert_eq!(board.moves_until_fifty_move_draw(), 0);
+    }
+
+    #[test]
+    fn piece_counts_track_captures() {
+        let mut board = Board::new();
+        let counts = board.piece_counts();
+        for color in [Color::White, Color::Black] {
+            assert_eq!(counts.get(color, PieceType::Pawn), 8);
+            assert_eq!(counts.get(color, PieceType::Knight), 2);
+            assert_eq!(counts.get(color, PieceType::Bishop), 2);
+            assert_eq!(counts.get(color, PieceType::Rook), 2);
+            assert_eq!(counts.get(color, PieceType::Queen), 1);
+            assert_eq!(counts.get(color, PieceType::King), 1);
+            assert_eq!(counts.total(color), 16);
+        }
+
+        // 1. e4 d5 2. exd5 Qxd5: each side loses a pawn.
+        board.apply_move(&mv((1, 4), (3, 4)));
+        board.apply_move(&mv((6, 3), (4, 3)));
+        board.apply_move(&mv((3, 4), (4, 3)));
+        board.apply_move(&mv((7, 3), (4, 3)));
+        let counts = board.piece_counts();
+        assert_eq!(counts.get(Color::White, PieceType::Pawn), 7);
+        assert_eq!(counts.get(Color::Black, PieceType::Pawn), 7);
+        assert_eq!(counts.get(Color::Black, PieceType::Queen), 1);
+        assert_eq!(counts.total(Color::White), 15);
+        assert_eq!(counts.total(Color::Black), 15);
+    }
+
+    /// White Ke1, Ra1, Rh1 with full castling rights, Black Ke8. Tests add
+    /// attackers on top of this.
+    fn castling_board() -> Board {
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[0][7] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.castling_rights.white_kingside = true;
+        board.castling_rights.white_queenside = true;
+        board
+    }
+
+    fn castles(board: &Board) -> Vec<(usize, usize)> {
+        board
+            .generate_legal_moves(board.current_turn)
+            .into_iter()
+            .filter(|m| board.is_castle(m))
+            .map(|m| m.to)
+            .collect()
+    }
+
+    /// Every generated castle must have the king out of check and its transit
+    /// and landing squares unattacked on the board it was generated from.
+    fn assert_no_illegal_castles(board: &Board) {
+        let color = board.current_turn;
+        for m in board.generate_legal_moves(color) {
+            let is_castle = board.squares[m.from.0][m.from.1]
+                .is_some_and(|p| p.piece_type == PieceType::King)
+                && m.from.1.abs_diff(m.to.1) == 2;
+            if !is_castle {
+                continue;
+            }
+            assert!(!board.is_in_check(color), "castled out of check: {}", m.to_uci());
+            let (lo, hi) = if m.to.1 > m.from.1 { (5, 6) } else { (2, 3) };
+            for col in lo..=hi {
+                assert!(!board.is_square_attacked_by(m.from.0, col, color.opposite()),
+                    "castled through an attacked square: {}", m.to_uci());
+            }
+        }
+    }
+
+    #[test]
+    fn castling_allowed_on_clear_board() {
+        assert_eq!(castles(&castling_board()), vec![(0, 6), (0, 2)]);
+    }
+
+    #[test]
+    fn castling_blocked_through_attacked_transit_square() {
+        // Black bishop on a6 hits f1 along the a6–f1 diagonal: no O-O.
+        let mut board = castling_board();
+        board.squares[5][0] = Some(Piece::new(PieceType::Bishop, Color::Black));
+        assert_eq!(castles(&board), vec![(0, 2)]);
+
+        // Black rook on d8 hits d1: no O-O-O.
+        let mut board = castling_board();
+        board.squares[7][3] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert_eq!(castles(&board), vec![(0, 6)]);
+    }
+
+    /// A rook on c1 attacks d1 directly, and would attack f1/g1 as soon as
+    /// the king left e1. The king is the only thing screening those squares,
+    /// so this is a check and neither castle may be offered.
+    #[test]
+    fn castling_blocked_when_king_screens_the_line() {
+        let mut board = castling_board();
+        board.squares[0][0] = None;
+        board.castling_rights.white_queenside = false;
+        board.squares[0][2] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert!(board.is_in_check(Color::White));
+        assert!(castles(&board).is_empty());
+    }
+
+    #[test]
+    fn generated_castles_are_always_legal() {
+        // Busy position with black sliders hovering over the white back rank.
+        let mut board = castling_board();
+        board.squares[7][0] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[5][2] = Some(Piece::new(PieceType::Bishop, Color::Black));
+        board.squares[4][6] = Some(Piece::new(PieceType::Queen, Color::Black));
+        board.squares[1][3] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        assert_no_illegal_castles(&board);
+
+        // And after every black reply.
+        let mut black_to_move = board.clone();
+        black_to_move.current_turn = Color::Black;
+        for black_move in black_to_move.generate_legal_moves(Color::Black) {
+            let mut after = black_to_move.clone();
+            after.apply_move(&black_move);
+            assert_no_illegal_castles(&after);
+        }
+    }
+
+    #[test]
+    fn castling_illegal_out_of_check() {
+        let mut board = castling_board();
+        board.squares[4][4] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert!(castles(&board).is_empty());
+    }
+
+    #[test]
+    fn castling_illegal_onto_attacked_square() {
+        // Rook on g8 covers g1; rook on c8 covers c1.
+        let mut board = castling_board();
+        board.squares[7][6] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert_eq!(castles(&board), vec![(0, 2)]);
+        let mut board = castling_board();
+        board.squares[7][2] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert_eq!(castles(&board), vec![(0, 6)]);
+    }
+
+    #[test]
+    fn castling_illegal_after_king_or_rook_moved() {
+        let play = |board: &mut Board, line: &[&str]| {
+            for uci in line {
+                board.apply_move(&Move::from_uci(uci).unwrap());
+            }
+        };
+        // The rook returns to h1 but the right is gone for good.
+        let mut board = castling_board();
+        play(&mut board, &["h1h2", "e8e7", "h2h1", "e7e8"]);
+        assert_eq!(castles(&board), vec![(0, 2)]);
+
+        let mut board = castling_board();
+        play(&mut board, &["e1d1", "e8e7", "d1e1", "e7e8"]);
+        assert!(castles(&board).is_empty());
+    }
+
+    #[test]
+    fn castling_illegal_through_pieces() {
+        // b1 is only crossed by the rook, but it must still be empty.
+        let mut board = castling_board();
+        board.squares[0][1] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[0][6] = Some(Piece::new(PieceType::Knight, Color::Black));
+        assert!(castles(&board).is_empty());
+    }
+
+    #[test]
+    fn castling_allowed_with_rook_or_b1_attacked() {
+        // Only the king's path matters: attacks on h1 (a rook in the
+        // corner can never be pinned to its own king) or on b1 don't.
+        let mut board = castling_board();
+        board.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[7][1] = Some(Piece::new(PieceType::Rook, Color::Black));
+        assert_eq!(castles(&board), vec![(0, 6), (0, 2)]);
+    }
+
+    #[test]
+    fn castling_follows_en_passant_discovered_attack() {
+        // White Ba3 aims at f8 through b4; ...cxb3 e.p. removes the blocker
+        // after b2-b4, and from then on Black may not castle through f8.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[2][0] = Some(Piece::new(PieceType::Bishop, Color::White));
+        board.squares[1][1] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[3][2] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.castling_rights.black_kingside = true;
+
+        board.apply_move(&Move::from_uci("b2b4").unwrap());
+        assert_eq!(castles(&board), vec![(7, 6)]);
+        board.apply_move(&Move::from_uci("c4b3").unwrap());
+        assert!(board.squares[3][1].is_none(), "en passant removes the b4 pawn");
+        board.apply_move(&Move::from_uci("e1d1").unwrap());
+        assert!(board.is_square_attacked_by(7, 5, Color::White));
+        assert!(castles(&board).is_empty());
+    }
+
+    #[test]
+    fn castling_rights_without_matching_pieces_are_ignored() {
+        // Rights can claim more than the board supports, e.g. a hand-edited
+        // position: no castle without our own rook in the corner and the
+        // king on e1.
+        let mut board = castling_board();
+        board.squares[0][7] = Some(Piece::new(PieceType::Bishop, Color::White));
+        board.squares[0][0] = None;
+        assert!(castles(&board).is_empty());
+
+        let mut board = castling_board();
+        board.squares[0][4] = None;
+        board.squares[0][3] = Some(Piece::new(PieceType::King, Color::White));
+        assert!(castles(&board).is_empty());
+    }
+
+    /// The start position hash is pinned for the default seed so that any
+    /// change to the hashing scheme is caught before it breaks external tables.
+    #[test]
+    fn start_position_hash_is_pinned() {
+        let board = Board::new();
+        assert_eq!(board.position_hash(), START_POSITION_HASH);
+        assert_eq!(Board::from_moves(Variant::Standard, &[]).unwrap().position_hash(), START_POSITION_HASH);
+        assert_eq!(board.position_hash(), board.position_hash_with(&Zobrist::with_seed(DEFAULT_ZOBRIST_SEED)));
+        assert_ne!(board.position_hash(), board.position_hash_with(&Zobrist::with_seed(1)));
+    }
 }
@@ -13,8 +13,13 @@
 // the AI's color. The draw penalty is perspective-independent.
 // =============================================================================
 
-use crate::board::Board;
-use crate::moves::Move;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::board::{splitmix64, Board, TerminalReason, Variant, CENTRE_SQUARES};
+use crate::moves::{index_to_square, Move};
+use crate::opening::book_moves;
 use crate::piece::{Color, PieceType};
 
 /// Platform-appropriate random number in [0, 1).
@@ -31,6 +36,32 @@ fn random_f64() -> f64 {
     }
 }
 
+/// Platform-appropriate clock in milliseconds, for search time limits.
+/// Natively it is monotonic (`Instant`, counted from the first call), so a
+/// wall-clock jump cannot cut a search short or stretch it. Uses
+/// js_sys::Date::now() in WASM builds, where `std` has no clock.
+fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        static EPOCH: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        EPOCH.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+    }
+}
+
+/// Pick an index in `0..n` for breaking ties between equal moves. Seeded
+/// configs derive it from the seed and the position so it is reproducible.
+fn tie_break_index(board: &Board, config: &AiConfig, n: usize) -> usize {
+    let index = match config.seed {
+        Some(seed) => (splitmix64(seed ^ board.position_hash()).1 % n as u64) as usize,
+        None => (random_f64() * n as f64) as usize,
+    };
+    index.min(n - 1)
+}
+
 // =============================================================================
 // Configuration
 // =============================================================================
@@ -47,6 +78,12 @@ pub struct Weights {
     pub centre_occupy: f64,
     /// Bonus per c3–f3/c6–f6 ring square attacked (the "extended centre").
     pub extended_centre_attack: f64,
+    /// Squares scored with `centre_attack` and `centre_occupy`, as
+    /// `(row, col)`. Defaults to `CENTRE_SQUARES` (d4, e4, d5, e5).
+    pub centre_squares: Vec<(usize, usize)>,
+    /// Squares scored with `extended_centre_attack`. Defaults to
+    /// `EXTENDED_CENTRE`.
+    pub extended_centre_squares: Vec<(usize, usize)>,
 
     // --- Passed pawn module ---
     /// Base bonus for a passed pawn (no enemy pawns ahead on same or adjacent files).
@@ -56,14 +93,68 @@ pub struct Weights {
     pub passed_pawn_quadratic: f64,
     /// Linear bonus per rank advanced for non-passed pawns (currently 0 = disabled).
     pub pawn_advance: f64,
+    /// Bonus for a rook behind a friendly passed pawn on its file (the
+    /// Tarrasch rule). A rook standing in front of an enemy passer, rather
+    /// than behind it, pays `ROOK_IN_FRONT_OF_PASSER_FRACTION` of this.
+    pub rook_behind_passer: f64,
+
+    // --- Outpost module ---
+    /// Bonus for a knight on an outpost: a square in enemy territory defended
+    /// by a friendly pawn that no enemy pawn can ever attack. Scaled up for
+    /// advanced and central outposts.
+    pub outpost_knight: f64,
+    /// As `outpost_knight`, for bishops.
+    pub outpost_bishop: f64,
+
+    // --- Rook placement module ---
+    /// Bonus for two friendly rooks on the same open file (no pawns). Half
+    /// as much on a half-open file (no friendly pawns).
+    pub doubled_rooks: f64,
+    /// Bonus for two rooks defending each other along their back rank.
+    pub connected_rooks: f64,
 
     // --- Mate / check module ---
     /// Penalty applied to the side currently in check (but not mated).
     pub check_penalty: f64,
+    /// Scales the check penalty up for checks that leave few replies: the
+    /// penalty is `check_penalty × (1 + check_restriction / replies)`.
+    pub check_restriction: f64,
+
+    // --- King attack module ---
+    /// Attack units per enemy-king-adjacent square hit by a knight or bishop.
+    pub king_attack_minor: f64,
+    /// Attack units per enemy-king-adjacent square hit by a rook.
+    pub king_attack_rook: f64,
+    /// Attack units per enemy-king-adjacent square hit by a queen.
+    pub king_attack_queen: f64,
+
+    // --- King safety module ---
+    /// Penalty per rank a shield pawn (the f/g/h or a/b/c pawns in front of a
+    /// castled king) has advanced, scaled by game phase. A missing shield
+    /// pawn counts as advanced two ranks.
+    pub shield_pawn_advance: f64,
+
+    // --- Space module ---
+    /// Bonus per square in the opponent's half that we attack and no enemy
+    /// pawn does.
+    pub space: f64,
+
+    // --- Development module ---
+    /// Penalty per knight or bishop still on its starting square, doubled
+    /// while the queen has already left home. Scaled by game phase.
+    pub development: f64,
+
+    // --- King of the Hill (variant only) ---
+    /// Bonus per step the king is closer to the nearest centre square.
+    pub koth_king_progress: f64,
 
     // --- Draw avoidance module ---
     /// Flat penalty applied when the current position has been seen before.
     pub repeat_penalty: f64,
+    /// The search scores a repetition draw at this much below zero for the
+    /// engine (and above zero for its opponent): its contempt for draws.
+    /// Keep it small enough that a clearly losing side still takes one.
+    pub draw_contempt: f64,
 }
 
 impl Default for Weights {
@@ -72,35 +163,139 @@ impl Default for Weights {
             centre_attack: 0.3,
             centre_occupy: 0.8,
             extended_centre_attack: 0.2,
+            centre_squares: CENTRE_SQUARES.to_vec(),
+            extended_centre_squares: EXTENDED_CENTRE.to_vec(),
             passed_pawn_base: 0.1,
             passed_pawn_quadratic: 0.3,
             pawn_advance: 0.0,
+            rook_behind_passer: 0.3,
+            outpost_knight: 0.3,
+            outpost_bishop: 0.15,
+            doubled_rooks: 0.3,
+            connected_rooks: 0.15,
             check_penalty: 2.0,
+            check_restriction: 1.0,
+            king_attack_minor: 0.1,
+            king_attack_rook: 0.15,
+            king_attack_queen: 0.25,
+            shield_pawn_advance: 0.3,
+            space: 0.05,
+            development: 0.15,
+            koth_king_progress: 1.0,
             repeat_penalty: 5.0,
+            draw_contempt: 0.5,
         }
     }
 }
 
+/// A replacement static evaluation: the score of the board from the given
+/// colour's point of view, in pawns. See `AiConfig::custom_eval`.
+pub type CustomEval = Arc<dyn Fn(&Board, Color) -> f64 + Send + Sync>;
+
 /// Controls which evaluation modules are active and the search depth.
 /// Each module can be toggled independently via the web UI.
 #[derive(Clone)]
 pub struct AiConfig {
-    /// Detect checkmate/stalemate and assign extreme scores (±10000 / -5000).
+    /// Penalize being in check and score finished draws as exactly 0.
+    /// Checkmate itself is always scored (±10000), whatever the modules.
     pub mate_module: bool,
+    /// With the mate module on, a leaf position where the side to move is in
+    /// check is searched one ply further instead of statically evaluated.
+    pub leaf_check_extension: bool,
     /// Count material advantage using standard piece values (P=1, N=B=3, R=5, Q=9).
     pub material_module: bool,
     /// Reward control and occupation of the centre squares.
     pub centre_module: bool,
     /// Reward passed pawns and penalize blocked pawns.
     pub passed_pawn_module: bool,
+    /// Reward knights and bishops on pawn-protected outposts.
+    pub outpost_module: bool,
+    /// Reward rooks doubled on open files and connected on the back rank.
+    pub rook_module: bool,
     /// Penalize positions that approach draws (repetition, 50-move rule).
     pub draw_penalty_module: bool,
-    /// Search depth in full moves (1–3). Internally converted to plies (depth×2).
+    /// Reward pieces bearing on the squares around the enemy king. Off by
+    /// default; enable for a more aggressive playing style.
+    pub attack_module: bool,
+    /// Penalize pushing the pawns in front of a castled king before the
+    /// endgame. Off by default.
+    pub king_safety_module: bool,
+    /// Reward controlling safe squares in the opponent's half. Off by
+    /// default.
+    pub space_module: bool,
+    /// Reward developing knights and bishops in the opening and discourage
+    /// early queen sorties. Off by default.
+    pub development_module: bool,
+    /// Search depth in full moves (0–3). Internally converted to plies (depth×2).
+    /// Depth 0 is a static greedy mode: each move is scored by `evaluate` on
+    /// the resulting position, with no search and no auto-deepening.
     pub depth: u32,
     /// When true, automatically increase depth until at least `min_evals` evaluations.
     pub auto_deepen: bool,
     /// Minimum number of static evaluations before auto-deepen stops.
     pub min_evals: u64,
+    /// When set, ties between equally scored moves are broken by a hash of
+    /// this seed and the position instead of the RNG, so the same position
+    /// always yields the same move.
+    pub seed: Option<u64>,
+    /// Remove randomness entirely: among tied moves, always pick the one
+    /// with the lowest UCI string. Takes precedence over `seed`.
+    pub deterministic: bool,
+    /// Search a fixed number of nodes instead of a fixed depth, for
+    /// hardware-independent strength comparisons. When set, `depth` and
+    /// `auto_deepen` are ignored: the search deepens until `max_nodes`
+    /// static evaluations have been spent.
+    pub max_nodes: Option<u64>,
+    /// Wall-clock limit for one search, in milliseconds. The search deepens
+    /// one ply at a time up to `depth` (and, with `auto_deepen`, beyond) and
+    /// plays the best move of the last iteration that finished in time.
+    /// Ignored at depth 0, which is instant anyway.
+    pub time_budget_ms: Option<u64>,
+    /// Use principal variation search: moves after the first are searched
+    /// with a null window and only re-searched if they beat it. Turn off to
+    /// compare against plain alpha-beta.
+    pub pvs: bool,
+    /// Time move generation, evaluation and move making during the search
+    /// and report it in `PickResult::profile`. Native only: ignored on wasm,
+    /// which has no clock in `std`.
+    pub profile: bool,
+    /// Stop searching further root moves (and stop auto-deepening) once a
+    /// move is found that forces mate. Off by default: the unsearched moves
+    /// are missing from `PickResult::root_stats`, which analysis needs.
+    pub stop_on_mate: bool,
+    /// Among root moves scoring within `KING_PRESSURE_MARGIN` of the best,
+    /// play the one that leaves the most attacks on the enemy king's
+    /// surroundings net of attacks on our own. Applied after `solid_play`
+    /// and `hang_check`, to the moves they keep. A playing-style knob; off
+    /// by default.
+    pub king_pressure_tiebreak: bool,
+    /// Before committing, look at the opponent's best immediate capture
+    /// against each of the top `HANG_CHECK_CANDIDATES` root moves within
+    /// `HANG_CHECK_MARGIN` of the best, and demote each by the material that
+    /// capture wins (see `best_reply_capture`): a cheap tactical check on
+    /// what the search may have missed at its horizon. Off by default.
+    pub hang_check: bool,
+    /// "Coach" personality: consider only root moves within `SOLID_MARGIN`
+    /// of the best, and among those play the one that leaves the opponent
+    /// the fewest forcing replies (checks, captures, promotions). Gives
+    /// solid, low-variance play. Off by default.
+    pub solid_play: bool,
+    /// While the position is in the opening book (`opening::book_moves`),
+    /// play the most popular book move instead of searching.
+    pub opening_book: bool,
+    /// With `opening_book`, choose among the book moves at random, weighted
+    /// by popularity (reproducibly if `seed` is set), so games vary in the
+    /// opening but only through book lines. Play is strict once out of book.
+    /// Ignored when `deterministic` is set.
+    pub book_variety: bool,
+    /// Teaching constraint: when above 0, the queen may not move before this
+    /// fullmove number unless nothing else is legal. A hard rule, unlike
+    /// the development module's soft penalty for early queen sorties.
+    pub no_early_queen_until: u32,
+    /// Evaluate positions with this function instead of the modules above,
+    /// e.g. to try a learned evaluation without touching the search.
+    /// Finished games (mate, draws) are still scored by the engine.
+    pub custom_eval: Option<CustomEval>,
     pub weights: Weights,
 }
 
@@ -114,13 +309,34 @@ impl AiConfig {
     pub fn new() -> Self {
         AiConfig {
             mate_module: true,
+            leaf_check_extension: true,
             material_module: true,
             centre_module: true,
             passed_pawn_module: true,
+            outpost_module: true,
+            rook_module: true,
             draw_penalty_module: true,
+            attack_module: false,
+            king_safety_module: false,
+            space_module: false,
+            development_module: false,
             depth: 2,
             auto_deepen: true,
             min_evals: 200_000,
+            seed: None,
+            deterministic: false,
+            max_nodes: None,
+            time_budget_ms: None,
+            pvs: true,
+            profile: false,
+            stop_on_mate: false,
+            king_pressure_tiebreak: false,
+            hang_check: false,
+            solid_play: false,
+            opening_book: false,
+            book_variety: false,
+            no_early_queen_until: 0,
+            custom_eval: None,
             weights: Weights::default(),
         }
     }
@@ -134,20 +350,127 @@ impl AiConfig {
 struct ScoredMove {
     mv: Move,
     score: f64,
+    /// Static evaluations spent searching this move's subtree.
+    nodes: u64,
+}
+
+/// Search statistics for one root move, in the order the moves were searched.
+#[derive(Clone, Debug)]
+pub struct RootMoveStats {
+    pub mv: Move,
+    /// Static evaluations spent in this move's subtree.
+    pub nodes: u64,
+    /// Search score from the mover's perspective.
+    pub score: f64,
 }
 
 /// The result of a move search, including the chosen move and the number of
 /// static evaluations performed during the search.
 pub struct PickResult {
     pub mv: Move,
+    /// Search score of `mv` from the mover's perspective.
+    pub score: f64,
+    /// Static evaluations in the final search iteration (with `max_nodes`
+    /// or `time_budget_ms`, across all iterations).
     pub evals: u64,
+    /// Per-root-move breakdown of the final search iteration. Without
+    /// `max_nodes`, the node counts sum to `evals`.
+    pub root_stats: Vec<RootMoveStats>,
+    /// Where the search spent its time, if `AiConfig::profile` was set.
+    pub profile: Option<SearchProfile>,
+}
+
+/// Time spent in each part of a search, accumulated over every node.
+#[derive(Clone, Debug, Default)]
+pub struct SearchProfile {
+    /// Generating and ordering legal moves.
+    pub movegen: Duration,
+    /// Static evaluation at the leaves.
+    pub eval: Duration,
+    /// Cloning the board and applying a move (including game-over detection).
+    pub make_move: Duration,
+    /// Wall time for the whole search.
+    pub total: Duration,
+}
+
+/// One ply of a game review from `analyze_game`. Scores are from the
+/// perspective of the side that played the move.
+pub struct AnalysisPoint {
+    pub ply: usize,
+    /// The engine's preferred move in the position.
+    pub best: Move,
+    pub best_score: f64,
+    /// The move actually played.
+    pub played: Move,
+    pub played_score: f64,
+    /// The played move scored at least `BLUNDER_THRESHOLD` below the best.
+    pub blunder: bool,
 }
 
-/// The four central squares: d4, d5, e4, e5.
-const CENTRE_SQUARES: [(usize, usize); 4] = [(3, 3), (3, 4), (4, 3), (4, 4)];
+/// Score drop (in pawns) from the best move that marks a played move as a
+/// blunder in `analyze_game`.
+pub const BLUNDER_THRESHOLD: f64 = 2.0;
+
+/// Score of delivering checkmate. The search scores a mate found `ply`
+/// half-moves from the root as `MATE_SCORE - ply`, so shorter mates win.
+const MATE_SCORE: f64 = 10000.0;
+
+/// Scores at or beyond this magnitude mean a forced mate was found.
+const MATE_THRESHOLD: f64 = 9000.0;
+
+/// Deepest iteration a `max_nodes` search will attempt.
+const MAX_SEARCH_PLIES: u32 = 12;
+
+/// Non-mate scores are clamped to this many pawns for display.
+const DISPLAY_EVAL_CAP: f64 = 10.0;
+
+/// Material lead (in pawns) above which the root safeguards consider the
+/// engine to be clearly winning.
+const WINNING_MATERIAL_LEAD: f64 = 3.0;
+
+/// Subtracted from the score of a root move that stalemates the opponent
+/// while the engine is clearly winning. Keeps it below any non-losing move.
+const STALEMATE_PENALTY: f64 = 1000.0;
+
+/// When clearly winning, a root move that repeats an earlier position loses
+/// this much, so any non-repeating move scoring within the margin wins out.
+const REPETITION_MARGIN: f64 = 0.5;
+
+/// When clearly winning with fewer than this many halfmoves left before the
+/// fifty-move draw, root moves that neither capture nor move a pawn are
+/// penalized, ramping up to `STALL_MARGIN` as the limit nears.
+const STALL_WARNING_HALFMOVES: u32 = 20;
+
+/// Largest penalty for a stalling root move (see `STALL_WARNING_HALFMOVES`).
+const STALL_MARGIN: f64 = 3.0;
+
+/// With `solid_play`, root moves scoring more than this many pawns below
+/// the best are never played.
+const SOLID_MARGIN: f64 = 0.3;
+
+/// With `king_pressure_tiebreak`, root moves scoring within this many pawns
+/// of the best count as near-equal.
+const KING_PRESSURE_MARGIN: f64 = 0.1;
+
+/// With `hang_check`, root moves scoring within this many pawns of the best
+/// are checked for a losing reply capture.
+const HANG_CHECK_MARGIN: f64 = 0.5;
+
+/// With `hang_check`, at most this many of the best-scoring root moves are
+/// checked.
+const HANG_CHECK_CANDIDATES: usize = 3;
+
+/// Scales the king-attack units by how many distinct pieces join the attack,
+/// indexed by attacker count. A lone attacker is rarely dangerous; three or
+/// more coordinated attackers usually are.
+const KING_ATTACKER_SCALE: [f64; 8] = [0.0, 0.25, 0.5, 0.75, 0.9, 0.95, 1.0, 1.0];
+
+/// Share of `rook_behind_passer` charged for a rook in front of an enemy
+/// passed pawn: blockading it is still better than ignoring it.
+const ROOK_IN_FRONT_OF_PASSER_FRACTION: f64 = 0.5;
 
 /// The 12 squares forming the "extended centre" ring around the inner 4.
-const EXTENDED_CENTRE: [(usize, usize); 12] = [
+pub const EXTENDED_CENTRE: [(usize, usize); 12] = [
     (2, 2), (2, 3), (2, 4), (2, 5),
     (3, 2), (3, 5),
     (4, 2), (4, 5),
@@ -159,25 +482,68 @@ const EXTENDED_CENTRE: [(usize, usize); 12] = [
 //
 // All modules score from White's perspective (positive = good for White).
 // evaluate() flips the sign when the AI plays Black.
+//
+// Score conventions:
+// - Evaluation modules: White's point of view.
+// - Search (negamax, PickResult::score, root stats): the side to move.
+// - UCI `info score cp`: the side to move; eval bars and the breakdown: White.
+// Use `evaluate_pov` rather than flipping signs by hand. The one term that
+// does not flip is the draw penalty: a repeated position costs whichever
+// side the score is for.
 // =============================================================================
 
+/// Whose point of view a score is from (see the conventions above).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pov {
+    /// The player to move, as in search and UCI output.
+    SideToMove,
+    /// White, as in a fixed eval bar.
+    White,
+    Color(Color),
+}
+
+impl Pov {
+    /// The colour this point of view resolves to on `board`.
+    pub fn color(self, board: &Board) -> Color {
+        match self {
+            Pov::SideToMove => board.current_turn,
+            Pov::White => Color::White,
+            Pov::Color(c) => c,
+        }
+    }
+}
+
 /// Standard piece values in pawns. The king has no material value since
 /// losing it means checkmate (handled by the mate module instead).
 fn piece_value(pt: PieceType) -> f64 {
-    match pt {
-        PieceType::Pawn => 1.0,
-        PieceType::Knight => 3.0,
-        PieceType::Bishop => 3.0,
-        PieceType::Rook => 5.0,
-        PieceType::Queen => 9.0,
-        PieceType::King => 0.0,
-    }
+    pt.value()
+}
+
+/// Total value (in pawns) of `color`'s pieces that have been captured.
+pub fn captured_material(board: &Board, color: Color) -> f64 {
+    let captured = match color {
+        Color::White => &board.captured_white,
+        Color::Black => &board.captured_black,
+    };
+    captured.iter().map(|&pt| piece_value(pt)).sum()
+}
+
+/// Evaluate the board from the point of view `pov`.
+pub fn evaluate_pov(board: &Board, pov: Pov, config: &AiConfig) -> f64 {
+    evaluate(board, pov.color(board), config)
 }
 
 /// Evaluate the board from `ai_color`'s perspective by summing all enabled
 /// modules. Positional modules score from White's perspective and are flipped
 /// for Black. The draw penalty is perspective-independent (always negative).
 pub fn evaluate(board: &Board, ai_color: Color, config: &AiConfig) -> f64 {
+    if let Some(score) = eval_terminal(board, config) {
+        return if ai_color == Color::Black { -score } else { score };
+    }
+    if let Some(custom) = &config.custom_eval {
+        return custom(board, ai_color);
+    }
+
     let mut score = 0.0;
 
     if config.mate_module {
@@ -190,7 +556,28 @@ pub fn evaluate(board: &Board, ai_color: Color, config: &AiConfig) -> f64 {
         score += eval_centre_control(board, &config.weights);
     }
     if config.passed_pawn_module {
-        score += eval_passed_pawns(board, &config.weights);
+        score += eval_pawn_structure(board, &config.weights) + eval_rooks_and_passers(board, &config.weights);
+    }
+    if config.outpost_module {
+        score += eval_outposts(board, &config.weights);
+    }
+    if config.rook_module {
+        score += eval_rooks(board, &config.weights);
+    }
+    if config.attack_module {
+        score += eval_king_attack(board, &config.weights);
+    }
+    if config.king_safety_module {
+        score += eval_king_safety(board, &config.weights);
+    }
+    if config.space_module {
+        score += eval_space(board, &config.weights);
+    }
+    if config.development_module {
+        score += eval_development(board, &config.weights);
+    }
+    if board.variant == Variant::KingOfTheHill {
+        score += eval_king_of_the_hill(board, &config.weights);
     }
 
     if ai_color == Color::Black { score = -score; }
@@ -210,19 +597,74 @@ pub struct EvalBreakdown {
     pub material: f64,
     pub centre: f64,
     pub passed_pawns: f64,
+    pub outposts: f64,
+    pub rooks: f64,
+    pub king_attack: f64,
+    pub king_safety: f64,
+    pub space: f64,
+    pub development: f64,
+    pub king_of_the_hill: f64,
     pub draw_penalty: f64,
     pub total: f64,
 }
 
 pub fn evaluate_breakdown(board: &Board, ai_color: Color, config: &AiConfig) -> EvalBreakdown {
     let flip = if ai_color == Color::Black { -1.0 } else { 1.0 };
+    if let Some(score) = eval_terminal(board, config) {
+        let mate = score * flip;
+        return EvalBreakdown {
+            mate, material: 0.0, centre: 0.0, passed_pawns: 0.0, outposts: 0.0, rooks: 0.0, king_attack: 0.0,
+            king_safety: 0.0, space: 0.0, development: 0.0, king_of_the_hill: 0.0, draw_penalty: 0.0, total: mate,
+        };
+    }
     let mate = if config.mate_module { eval_mate(board, &config.weights) * flip } else { 0.0 };
     let material = if config.material_module { eval_material(board) * flip } else { 0.0 };
     let centre = if config.centre_module { eval_centre_control(board, &config.weights) * flip } else { 0.0 };
-    let passed_pawns = if config.passed_pawn_module { eval_passed_pawns(board, &config.weights) * flip } else { 0.0 };
+    let passed_pawns = if config.passed_pawn_module {
+        (eval_pawn_structure(board, &config.weights) + eval_rooks_and_passers(board, &config.weights)) * flip
+    } else {
+        0.0
+    };
+    let outposts = if config.outpost_module { eval_outposts(board, &config.weights) * flip } else { 0.0 };
+    let rooks = if config.rook_module { eval_rooks(board, &config.weights) * flip } else { 0.0 };
+    let king_attack = if config.attack_module { eval_king_attack(board, &config.weights) * flip } else { 0.0 };
+    let king_safety = if config.king_safety_module { eval_king_safety(board, &config.weights) * flip } else { 0.0 };
+    let space = if config.space_module { eval_space(board, &config.weights) * flip } else { 0.0 };
+    let development = if config.development_module { eval_development(board, &config.weights) * flip } else { 0.0 };
+    let king_of_the_hill = if board.variant == Variant::KingOfTheHill { eval_king_of_the_hill(board, &config.weights) * flip } else { 0.0 };
     let draw_penalty = if config.draw_penalty_module { eval_draw_penalty(board, &config.weights) } else { 0.0 };
-    let total = mate + material + centre + passed_pawns + draw_penalty;
-    EvalBreakdown { mate, material, centre, passed_pawns, draw_penalty, total }
+    let total = mate + material + centre + passed_pawns + outposts + rooks + king_attack + king_safety + space + development + king_of_the_hill + draw_penalty;
+    EvalBreakdown { mate, material, centre, passed_pawns, outposts, rooks, king_attack, king_safety, space, development, king_of_the_hill, draw_penalty, total }
+}
+
+/// White's-perspective `EvalBreakdown` of the last position asked for, keyed
+/// by `position_hash`, so a UI polling an unchanged board doesn't rerun every
+/// module. The hash doesn't cover the config or the repetition history:
+/// owners must `clear` it whenever either may have changed.
+#[derive(Clone, Debug, Default)]
+pub struct BreakdownCache {
+    entry: Option<(u64, EvalBreakdown)>,
+    computations: u64,
+}
+
+impl BreakdownCache {
+    pub fn get(&mut self, board: &Board, config: &AiConfig) -> &EvalBreakdown {
+        let hash = board.position_hash();
+        if self.entry.as_ref().is_none_or(|(h, _)| *h != hash) {
+            self.computations += 1;
+            self.entry = Some((hash, evaluate_breakdown(board, Color::White, config)));
+        }
+        &self.entry.as_ref().expect("just filled").1
+    }
+
+    pub fn clear(&mut self) {
+        self.entry = None;
+    }
+
+    /// Times `get` has had to evaluate the position.
+    pub fn computations(&self) -> u64 {
+        self.computations
+    }
 }
 
 // =============================================================================
@@ -232,16 +674,20 @@ pub fn evaluate_breakdown(board: &Board, ai_color: Color, config: &AiConfig) ->
 /// Material: sum piece values for each side. A simple count of who has more
 /// "stuff" on the board. This is the strongest signal for positional strength.
 fn eval_material(board: &Board) -> f64 {
-    let mut score = 0.0;
-    for row in 0..8 {
-        for col in 0..8 {
-            if let Some(p) = board.squares[row][col] {
-                let v = piece_value(p.piece_type);
-                if p.color == Color::White { score += v; } else { score -= v; }
-            }
-        }
-    }
-    score
+    let counts = board.piece_counts();
+    PieceType::ALL
+        .iter()
+        .map(|&pt| {
+            let diff = counts.get(Color::White, pt) as f64 - counts.get(Color::Black, pt) as f64;
+            diff * piece_value(pt)
+        })
+        .sum()
+}
+
+/// Material balance from `color`'s perspective (positive = ahead).
+fn material_lead(board: &Board, color: Color) -> f64 {
+    let m = eval_material(board);
+    if color == Color::White { m } else { -m }
 }
 
 /// Centre control: rewards attacking and occupying the four central squares
@@ -250,7 +696,7 @@ fn eval_material(board: &Board) -> f64 {
 fn eval_centre_control(board: &Board, w: &Weights) -> f64 {
     let mut score = 0.0;
 
-    for &(r, c) in &CENTRE_SQUARES {
+    for &(r, c) in &w.centre_squares {
         if board.is_square_attacked_by(r, c, Color::White) {
             score += w.centre_attack;
         }
@@ -262,7 +708,7 @@ fn eval_centre_control(board: &Board, w: &Weights) -> f64 {
         }
     }
 
-    for &(r, c) in &EXTENDED_CENTRE {
+    for &(r, c) in &w.extended_centre_squares {
         if board.is_square_attacked_by(r, c, Color::White) {
             score += w.extended_centre_attack;
         }
@@ -274,24 +720,102 @@ fn eval_centre_control(board: &Board, w: &Weights) -> f64 {
     score
 }
 
-/// Mate and check detection: assigns extreme scores to checkmate, a large
-/// penalty to stalemate (draw), and a smaller penalty for being in check.
-fn eval_mate(board: &Board, w: &Weights) -> f64 {
-    let in_check = board.is_in_check(board.current_turn);
+/// Terminal positions, scored whichever modules are enabled so that a mated
+/// side never looks merely "down material". Checkmate is always ±10000. With
+/// the mate module on, a game that `apply_move` has ended in a draw
+/// (stalemate, repetition, …) scores exactly 0; with it off, drawn positions
+/// fall through to the normal modules.
+fn eval_terminal(board: &Board, config: &AiConfig) -> Option<f64> {
+    let mated = board.winner().is_some()
+        || (board.is_in_check(board.current_turn)
+            && (board.game_over || board.legal_move_count(board.current_turn) == 0));
+    if mated {
+        // The side to move has lost
+        return Some(if board.current_turn == Color::White { -MATE_SCORE } else { MATE_SCORE });
+    }
+    if config.mate_module && board.game_over {
+        return Some(0.0);
+    }
+    None
+}
 
-    if !in_check {
+/// Check penalty: a penalty for the side currently in check (but not mated,
+/// which `eval_terminal` has already handled). The fewer legal replies the
+/// check leaves, the larger the penalty.
+fn eval_mate(board: &Board, w: &Weights) -> f64 {
+    if !board.is_in_check(board.current_turn) {
         return 0.0;
     }
+    let replies = board.legal_move_count(board.current_turn).max(1) as f64;
+    let penalty = w.check_penalty * (1.0 + w.check_restriction / replies);
+    if board.current_turn == Color::White { -penalty } else { penalty }
+}
 
-    let no_moves = board.game_over || board.generate_legal_moves(board.current_turn).is_empty();
+/// Entries in the per-thread pawn hash table.
+const PAWN_CACHE_SIZE: usize = 1 << 12;
 
-    if no_moves {
-        // Checkmate — the side to move has lost
-        if board.current_turn == Color::White { -10000.0 } else { 10000.0 }
-    } else {
-        // In check but can escape — slight penalty for the checked side
-        if board.current_turn == Color::White { -w.check_penalty } else { w.check_penalty }
-    }
+/// One pawn hash table slot: the pawn hash, the weights the score was
+/// computed with (so retuned weights never read stale scores), and the score.
+#[derive(Clone, Copy)]
+struct PawnEntry {
+    key: u64,
+    weights: [u64; 3],
+    score: f64,
+}
+
+/// Pawn-structure scores by `Board::pawn_hash`. Pawns move rarely, so most
+/// nodes of a search share their pawn placement with a position already
+/// scored. Always-replace, indexed by the low bits of the hash.
+struct PawnCache {
+    entries: Vec<Option<PawnEntry>>,
+    stats: PawnCacheStats,
+}
+
+/// Lookups served by this thread's pawn hash table since it was last reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PawnCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+thread_local! {
+    static PAWN_CACHE: RefCell<PawnCache> = RefCell::new(PawnCache {
+        entries: vec![None; PAWN_CACHE_SIZE],
+        stats: PawnCacheStats::default(),
+    });
+}
+
+/// Hits and misses of the calling thread's pawn hash table.
+pub fn pawn_cache_stats() -> PawnCacheStats {
+    PAWN_CACHE.with(|cache| cache.borrow().stats)
+}
+
+/// Empty the calling thread's pawn hash table and zero its statistics.
+pub fn reset_pawn_cache() {
+    PAWN_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        cache.entries.fill(None);
+        cache.stats = PawnCacheStats::default();
+    });
+}
+
+/// Pawn-structure terms (currently the passed pawn module), through the
+/// pawn hash table.
+fn eval_pawn_structure(board: &Board, w: &Weights) -> f64 {
+    let key = board.pawn_hash();
+    let weights = [w.passed_pawn_base.to_bits(), w.passed_pawn_quadratic.to_bits(), w.pawn_advance.to_bits()];
+    let slot = key as usize % PAWN_CACHE_SIZE;
+    PAWN_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(entry) = cache.entries[slot].filter(|e| e.key == key && e.weights == weights) {
+            cache.stats.hits += 1;
+            return entry.score;
+        }
+        cache.stats.misses += 1;
+        let score = eval_passed_pawns(board, w);
+        cache.entries[slot] = Some(PawnEntry { key, weights, score });
+        score
+    })
 }
 
 /// Check whether a pawn is "passed" — no enemy pawns ahead of it on the
@@ -359,18 +883,358 @@ fn eval_passed_pawns(board: &Board, w: &Weights) -> f64 {
     score
 }
 
+/// Rooks and passed pawns (the Tarrasch rule): a rook on a passed pawn's
+/// file with nothing between them scores `rook_behind_passer` if it is
+/// behind its own passer, and loses a fraction of that if it stands in
+/// front of an enemy passer. A rook in front of its own passer gets nothing.
+fn eval_rooks_and_passers(board: &Board, w: &Weights) -> f64 {
+    let mut score = 0.0;
+    for row in 0..8usize {
+        for col in 0..8usize {
+            let pawn = match board.squares[row][col] {
+                Some(p) if p.piece_type == PieceType::Pawn && is_passed_pawn(board, row, col, p.color) => p,
+                _ => continue,
+            };
+            // Walk away from the pawn both ways to the first piece on the file.
+            for step in [1i32, -1] {
+                let mut r = row as i32 + step;
+                while (0..8).contains(&r) && board.squares[r as usize][col].is_none() {
+                    r += step;
+                }
+                let rook = match (0..8).contains(&r).then(|| board.squares[r as usize][col]).flatten() {
+                    Some(p) if p.piece_type == PieceType::Rook => p,
+                    _ => continue,
+                };
+                let ahead = (step > 0) == (pawn.color == Color::White);
+                let bonus = if rook.color == pawn.color && !ahead {
+                    w.rook_behind_passer
+                } else if rook.color != pawn.color && ahead {
+                    -w.rook_behind_passer * ROOK_IN_FRONT_OF_PASSER_FRACTION
+                } else {
+                    0.0
+                };
+                score += if rook.color == Color::White { bonus } else { -bonus };
+            }
+        }
+    }
+    score
+}
+
+/// True if the `color` piece at (row, col) sits on an outpost: in enemy
+/// territory (ranks 4–6 from its side), defended by a friendly pawn, and on
+/// a square no enemy pawn can advance to attack.
+fn is_outpost(board: &Board, row: usize, col: usize, color: Color) -> bool {
+    // Rank index from `color`'s side: 3..=5 is ranks 4–6.
+    let relative_rank = match color {
+        Color::White => row,
+        Color::Black => 7 - row,
+    };
+    if !(3..=5).contains(&relative_rank) {
+        return false;
+    }
+    let is_pawn = |r: usize, c: usize, pc: Color| {
+        matches!(board.squares[r][c], Some(p) if p.piece_type == PieceType::Pawn && p.color == pc)
+    };
+    // Enemy pawns that could advance to attack come from any rank ahead.
+    let ahead = match color {
+        Color::White => row + 1..8,
+        Color::Black => 0..row,
+    };
+    let files: Vec<usize> = [col.wrapping_sub(1), col + 1].into_iter().filter(|&c| c < 8).collect();
+    let defended = board.pawn_attacks_square(row, col, color);
+    let attackable = files.iter().any(|&c| ahead.clone().any(|r| is_pawn(r, c, color.opposite())));
+    defended && !attackable
+}
+
+/// Outposts: rewards knights (and, less, bishops) on outpost squares. The
+/// bonus grows by half again per rank beyond the 4th and is halved on the
+/// a, b, g and h files.
+fn eval_outposts(board: &Board, w: &Weights) -> f64 {
+    let mut score = 0.0;
+    for row in 0..8usize {
+        for col in 0..8usize {
+            let p = match board.squares[row][col] {
+                Some(p) => p,
+                None => continue,
+            };
+            let weight = match p.piece_type {
+                PieceType::Knight => w.outpost_knight,
+                PieceType::Bishop => w.outpost_bishop,
+                _ => continue,
+            };
+            if !is_outpost(board, row, col, p.color) {
+                continue;
+            }
+            let advance = match p.color {
+                Color::White => row - 3,
+                Color::Black => 4 - row,
+            };
+            let centrality = if (2..=5).contains(&col) { 1.0 } else { 0.5 };
+            let bonus = weight * (1.0 + 0.5 * advance as f64) * centrality;
+            score += if p.color == Color::White { bonus } else { -bonus };
+        }
+    }
+    score
+}
+
+/// Rook placement: rewards two rooks doubled on an open (or, at half the
+/// bonus, half-open) file, and two rooks connected on their back rank with
+/// nothing between them.
+fn eval_rooks(board: &Board, w: &Weights) -> f64 {
+    let mut score = 0.0;
+    for color in [Color::White, Color::Black] {
+        let is = |row: usize, col: usize, pt: PieceType, c: Color| {
+            board.squares[row][col].is_some_and(|p| p.piece_type == pt && p.color == c)
+        };
+        let mut bonus = 0.0;
+        for col in 0..8usize {
+            let rooks = (0..8).filter(|&r| is(r, col, PieceType::Rook, color)).count();
+            if rooks < 2 || (0..8).any(|r| is(r, col, PieceType::Pawn, color)) {
+                continue;
+            }
+            let open = !(0..8).any(|r| is(r, col, PieceType::Pawn, color.opposite()));
+            bonus += if open { w.doubled_rooks } else { w.doubled_rooks * 0.5 };
+        }
+        // Walk the back rank: a rook whose nearest neighbour to the left is
+        // also one of our rooks is connected to it.
+        let back = if color == Color::White { 0 } else { 7 };
+        let mut rook_to_left = false;
+        for col in 0..8usize {
+            if board.squares[back][col].is_none() {
+                continue;
+            }
+            let rook = is(back, col, PieceType::Rook, color);
+            if rook && rook_to_left {
+                bonus += w.connected_rooks;
+            }
+            rook_to_left = rook;
+        }
+        score += if color == Color::White { bonus } else { -bonus };
+    }
+    score
+}
+
+/// Material the opponent wins, by static exchange, with its best capture
+/// after `mv`; 0 if it has no winning capture. Only the best one counts: the
+/// reply can make just one.
+fn best_reply_capture(board: &Board, mv: &Move) -> f64 {
+    let us = board.current_turn;
+    let mut child = board.clone();
+    child.apply_move(mv);
+    child
+        .hanging_pieces(us)
+        .into_iter()
+        .filter_map(|(r, c)| child.see(r, c, us.opposite()))
+        .fold(0.0, f64::max)
+}
+
+/// Attacks by `attacker` on the enemy king's square and its neighbours,
+/// counting every attacking piece per square.
+fn king_zone_attacks(board: &Board, attacker: Color) -> u32 {
+    let Some((kr, kc)) = board.find_king(attacker.opposite()) else {
+        return 0;
+    };
+    let mut total = 0;
+    for r in kr.saturating_sub(1)..=(kr + 1).min(7) {
+        for c in kc.saturating_sub(1)..=(kc + 1).min(7) {
+            total += board.attack_count(r, c, attacker);
+        }
+    }
+    total
+}
+
+/// Attack units `attacker` has on the squares adjacent to the enemy king,
+/// scaled by the number of distinct attacking pieces (pawns and the king
+/// itself don't count as attackers).
+fn king_attack_units(board: &Board, attacker: Color, w: &Weights) -> f64 {
+    let (kr, kc) = match board.find_king(attacker.opposite()) {
+        Some(k) => k,
+        None => return 0.0,
+    };
+    let mut units = 0.0;
+    let mut attackers = 0usize;
+    for row in 0..8usize {
+        for col in 0..8usize {
+            let p = match board.squares[row][col] {
+                Some(p) if p.color == attacker => p,
+                _ => continue,
+            };
+            let unit = match p.piece_type {
+                PieceType::Knight | PieceType::Bishop => w.king_attack_minor,
+                PieceType::Rook => w.king_attack_rook,
+                PieceType::Queen => w.king_attack_queen,
+                PieceType::Pawn | PieceType::King => continue,
+            };
+            let hits = board
+                .attacks_from(row, col)
+                .iter()
+                .filter(|&&(r, c)| (r, c) != (kr, kc) && r.abs_diff(kr) <= 1 && c.abs_diff(kc) <= 1)
+                .count();
+            if hits > 0 {
+                attackers += 1;
+                units += hits as f64 * unit;
+            }
+        }
+    }
+    units * KING_ATTACKER_SCALE[attackers.min(KING_ATTACKER_SCALE.len() - 1)]
+}
+
+/// King attack: rewards pieces bearing on the enemy king's surroundings.
+/// The attacking counterpart of king safety.
+fn eval_king_attack(board: &Board, w: &Weights) -> f64 {
+    king_attack_units(board, Color::White, w) - king_attack_units(board, Color::Black, w)
+}
+
+/// Non-pawn material left, from 1.0 in the opening to 0.0 with only kings
+/// and pawns (see `Board::game_phase`).
+pub fn game_phase(board: &Board) -> f64 {
+    board.game_phase()
+}
+
+/// Ranks `color`'s shield pawns have advanced in front of its castled king
+/// (0 if the king is uncastled or off its back rank).
+fn shield_pawn_advance(board: &Board, color: Color) -> u32 {
+    let (home, pawn_home) = match color {
+        Color::White => (0usize, 1usize),
+        Color::Black => (7, 6),
+    };
+    let files = match board.find_king(color) {
+        Some((kr, kc)) if kr == home && kc >= 5 => 5..8,
+        Some((kr, kc)) if kr == home && kc <= 2 => 0..3,
+        _ => return 0,
+    };
+    files
+        .map(|col| {
+            (0..8usize)
+                .filter(|&row| matches!(board.squares[row][col], Some(p) if p.piece_type == PieceType::Pawn && p.color == color))
+                .map(|row| row.abs_diff(pawn_home) as u32)
+                .min()
+                .unwrap_or(2)
+                .min(2)
+        })
+        .sum()
+}
+
+/// King safety: penalizes pushing the pawn shield of a castled king. Fades
+/// out with the game phase and is off in the endgame, where the king should
+/// become active.
+fn eval_king_safety(board: &Board, w: &Weights) -> f64 {
+    if board.is_endgame() {
+        return 0.0;
+    }
+    let advance = shield_pawn_advance(board, Color::Black) as f64 - shield_pawn_advance(board, Color::White) as f64;
+    advance * w.shield_pawn_advance * game_phase(board)
+}
+
+/// Squares on `color`'s opponent's half (ranks 5–8 for White, 1–4 for
+/// Black) that `color` attacks and no enemy pawn does.
+fn space_squares(board: &Board, color: Color) -> u32 {
+    let rows = match color {
+        Color::White => 4..8usize,
+        Color::Black => 0..4usize,
+    };
+    let mut count = 0;
+    for row in rows {
+        for col in 0..8usize {
+            if board.attack_count(row, col, color) > 0 && !board.pawn_attacks_square(row, col, color.opposite()) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// Space: rewards territory, the safe squares each side controls in the
+/// other's half of the board.
+fn eval_space(board: &Board, w: &Weights) -> f64 {
+    (space_squares(board, Color::White) as f64 - space_squares(board, Color::Black) as f64) * w.space
+}
+
+/// `color`'s knights and bishops still on their starting squares.
+fn undeveloped_minors(board: &Board, color: Color) -> u32 {
+    let home = if color == Color::White { 0 } else { 7 };
+    [(1, PieceType::Knight), (6, PieceType::Knight), (2, PieceType::Bishop), (5, PieceType::Bishop)]
+        .iter()
+        .filter(|&&(col, pt)| board.squares[home][col].is_some_and(|p| p.piece_type == pt && p.color == color))
+        .count() as u32
+}
+
+/// Development: penalizes minor pieces left at home, twice as much once the
+/// queen has come out before them. Fades out with the game phase and is
+/// off in the endgame.
+fn eval_development(board: &Board, w: &Weights) -> f64 {
+    if board.is_endgame() {
+        return 0.0;
+    }
+    let lag = |color: Color| {
+        let home = if color == Color::White { 0 } else { 7 };
+        let queen_home = board.squares[home][3].is_some_and(|p| p.piece_type == PieceType::Queen && p.color == color);
+        let queen_out = !queen_home && board.piece_counts().get(color, PieceType::Queen) > 0;
+        undeveloped_minors(board, color) as f64 * if queen_out { 2.0 } else { 1.0 }
+    };
+    (lag(Color::Black) - lag(Color::White)) * w.development * game_phase(board)
+}
+
+/// King of the Hill: a king on the hill has won; otherwise reward each king
+/// for being close to it (Chebyshev distance to the nearest centre square).
+fn eval_king_of_the_hill(board: &Board, w: &Weights) -> f64 {
+    let mut score = 0.0;
+    for (color, s) in [(Color::White, 1.0), (Color::Black, -1.0)] {
+        if board.king_on_hill(color) {
+            return s * 10000.0;
+        }
+        if let Some((kr, kc)) = board.find_king(color) {
+            let distance = CENTRE_SQUARES
+                .iter()
+                .map(|&(r, c)| r.abs_diff(kr).max(c.abs_diff(kc)))
+                .min()
+                .unwrap_or(0);
+            score += s * w.koth_king_progress * (3 - distance.min(3)) as f64;
+        }
+    }
+    score
+}
+
 /// Draw avoidance: applies a flat penalty if the current position has been
 /// seen before in the game. Discourages the AI from repeating positions.
 fn eval_draw_penalty(board: &Board, w: &Weights) -> f64 {
-    let current_hash = board.position_hash();
-    let repeat_count = board.position_history.iter().filter(|&&h| h == current_hash).count();
-    if repeat_count >= 2 {
+    if board.repetition_count() >= 2 {
         -(w.repeat_penalty)
     } else {
         0.0
     }
 }
 
+// =============================================================================
+// Batch evaluation
+// =============================================================================
+
+/// Statically evaluate each position from its side to move's perspective
+/// (the same convention as negamax leaves). Handy for labeling datasets.
+pub fn evaluate_many(positions: &[Board], config: &AiConfig) -> Vec<f64> {
+    positions
+        .iter()
+        .map(|board| evaluate_pov(board, Pov::SideToMove, config))
+        .collect()
+}
+
+/// `evaluate_many` split across `threads` native threads. Results are in
+/// the same order as `positions`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn evaluate_many_parallel(positions: &[Board], config: &AiConfig, threads: usize) -> Vec<f64> {
+    let chunk_size = positions.len().div_ceil(threads.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = positions
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || evaluate_many(chunk, config)))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("evaluation thread panicked"))
+            .collect()
+    })
+}
+
 // =============================================================================
 // Move ordering
 // =============================================================================
@@ -382,7 +1246,8 @@ fn eval_draw_penalty(board: &Board, w: &Weights) -> f64 {
 ///   1. Promotions (queen promotion highest)
 ///   2. Captures, ordered by MVV-LVA (Most Valuable Victim, Least Valuable
 ///      Attacker) — e.g. pawn takes queen is searched before queen takes queen
-///   3. Quiet moves (score 0)
+///   3. Quiet moves, with a small bonus for non-king moves into the centre
+///      (2) or extended centre (1) so development is tried before shuffles
 fn move_priority(board: &Board, mv: &Move) -> i32 {
     let mut score = 0;
 
@@ -390,25 +1255,123 @@ fn move_priority(board: &Board, mv: &Move) -> i32 {
         score += 900 + piece_value(promo) as i32;
     }
 
-    if let Some(victim) = board.squares[mv.to.0][mv.to.1] {
+    if board.is_capture(mv) {
+        // En passant leaves the target square empty; the victim is a pawn
+        let victim = board.squares[mv.to.0][mv.to.1].map_or(PieceType::Pawn, |p| p.piece_type);
         let attacker = board.squares[mv.from.0][mv.from.1]
             .map(|p| piece_value(p.piece_type) as i32)
             .unwrap_or(0);
-        score += 100 + piece_value(victim.piece_type) as i32 * 10 - attacker;
+        score += 100 + piece_value(victim) as i32 * 10 - attacker;
+    } else if mv.promotion.is_none() {
+        let is_king = board.squares[mv.from.0][mv.from.1]
+            .is_some_and(|p| p.piece_type == PieceType::King);
+        if !is_king {
+            if CENTRE_SQUARES.contains(&mv.to) {
+                score += 2;
+            } else if EXTENDED_CENTRE.contains(&mv.to) {
+                score += 1;
+            }
+        }
     }
 
     score
 }
 
-/// Sort moves so the most promising are searched first.
+/// Sort key that orders moves the same way as their UCI strings ("a2a3" <
+/// "a2a4" < "b1c3"), without allocating the strings.
+fn uci_order_key(mv: &Move) -> (usize, usize, usize, usize, u8) {
+    let promo = match mv.promotion {
+        None => 0,
+        Some(PieceType::Bishop) => 1,
+        Some(PieceType::Knight) => 2,
+        Some(PieceType::Queen) => 3,
+        Some(_) => 4,
+    };
+    (mv.from.1, mv.from.0, mv.to.1, mv.to.0, promo)
+}
+
+/// Sort moves so the most promising are searched first. Equal priorities are
+/// broken by UCI order, so the result depends only on the position, not on
+/// generation order, and seeded tie-breaks agree across platforms.
 fn order_moves(board: &Board, moves: &mut [Move]) {
-    moves.sort_by_key(|mv| std::cmp::Reverse(move_priority(board, mv)));
+    moves.sort_by_key(|mv| (std::cmp::Reverse(move_priority(board, mv)), uci_order_key(mv)));
 }
 
 // =============================================================================
 // Search — Negamax with alpha-beta pruning
 // =============================================================================
 
+/// Bookkeeping threaded through the search: the running node count and the
+/// limits that can interrupt it.
+struct SearchState {
+    evals: u64,
+    max_nodes: Option<u64>,
+    /// `now_ms()` past which the search stops, from `time_budget_ms`.
+    deadline_ms: Option<f64>,
+    /// `should_stop` calls, so the clock is only read every so often.
+    polls: u32,
+    /// Latched once a limit is hit. Every search frame then unwinds at once
+    /// and its scores are meaningless.
+    stopped: bool,
+    /// Timings, collected only when profiling.
+    profile: Option<SearchProfile>,
+}
+
+impl SearchState {
+    fn new(config: &AiConfig) -> Self {
+        let profile = (config.profile && cfg!(not(target_arch = "wasm32"))).then(SearchProfile::default);
+        let deadline_ms = config.time_budget_ms.map(|ms| now_ms() + ms as f64);
+        SearchState { evals: 0, max_nodes: config.max_nodes, deadline_ms, polls: 0, stopped: false, profile }
+    }
+
+    /// Run `f`, adding its duration to the `slot` timing when profiling.
+    fn timed<T>(&mut self, slot: fn(&mut SearchProfile) -> &mut Duration, f: impl FnOnce() -> T) -> T {
+        match self.profile.as_mut() {
+            None => f(),
+            Some(profile) => {
+                let start = Instant::now();
+                let result = f();
+                *slot(profile) += start.elapsed();
+                result
+            }
+        }
+    }
+
+    /// Check the limits, latching `stopped` once one is exceeded.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped && self.max_nodes.is_some_and(|max| self.evals >= max) {
+            self.stopped = true;
+        }
+        if !self.stopped && self.deadline_ms.is_some() {
+            self.polls = self.polls.wrapping_add(1);
+            if self.polls.is_multiple_of(CLOCK_POLL_INTERVAL) && self.deadline_ms.is_some_and(|d| now_ms() >= d) {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+}
+
+/// With a time budget, the clock is read once per this many search nodes.
+const CLOCK_POLL_INTERVAL: u32 = 256;
+
+/// Leaf check extensions stop at this ply, bounding long checking sequences.
+const MAX_CHECK_EXTENSION_PLY: u32 = 24;
+
+/// Width of the null window used by principal variation search.
+const PVS_EPSILON: f64 = 1e-6;
+
+/// Score of a repetition draw for the side to move at `ply` in the search.
+/// The engine moves at even plies; with the draw-penalty module it values
+/// the draw at `-draw_contempt`, so its opponent values it at the reverse.
+fn repetition_score(ply: u32, config: &AiConfig) -> f64 {
+    if !config.draw_penalty_module {
+        return 0.0;
+    }
+    let contempt = config.weights.draw_contempt;
+    if ply.is_multiple_of(2) { -contempt } else { contempt }
+}
+
 /// Negamax search with alpha-beta pruning.
 ///
 /// Scores are always from the current player's perspective (positive = good
@@ -417,44 +1380,94 @@ fn order_moves(board: &Board, moves: &mut [Move]) {
 ///
 /// Moves are ordered before searching so that captures and promotions are
 /// tried first, which causes alpha-beta to prune much more aggressively.
+///
+/// `ply` is the distance from the root, used to prefer shorter mates.
 fn negamax(
     board: &Board,
     depth: u32,
+    ply: u32,
     mut alpha: f64,
     beta: f64,
     config: &AiConfig,
-    evals: &mut u64,
+    state: &mut SearchState,
 ) -> f64 {
+    // Out of budget: unwind. The caller discards the interrupted result.
+    if state.should_stop() {
+        return 0.0;
+    }
+
     // Terminal: game already decided by apply_move (checkmate, stalemate, etc.)
     if board.game_over {
-        *evals += 1;
-        if board.is_in_check(board.current_turn) {
-            return -10000.0 - depth as f64;
+        state.evals += 1;
+        // Side to move has lost: checkmated, or (King of the Hill) the
+        // opponent's king reached the centre.
+        if board.is_in_check(board.current_turn) || board.winner().is_some() {
+            return -(MATE_SCORE - ply as f64);
         }
-        return 0.0;
+        return if board.is_repetition() { repetition_score(ply, config) } else { 0.0 };
     }
 
+    // A position repeated from earlier in the game or in this line is a
+    // draw: whoever is worse off can keep repeating it. This lets a losing
+    // side find a perpetual check, and a winning side avoid one.
+    if board.is_repetition() {
+        state.evals += 1;
+        return repetition_score(ply, config);
+    }
+
+    // Don't stand pat in check: the static eval can't see what the check
+    // wins or loses, so search the replies one ply further.
+    let extend = depth == 0
+        && config.mate_module
+        && config.leaf_check_extension
+        && ply < MAX_CHECK_EXTENSION_PLY
+        && board.is_in_check(board.current_turn);
+    let depth = if extend { 1 } else { depth };
+
     if depth == 0 {
-        *evals += 1;
-        return evaluate(board, board.current_turn, config);
+        state.evals += 1;
+        return state.timed(|p| &mut p.eval, || evaluate_pov(board, Pov::SideToMove, config));
     }
 
-    let mut legal_moves = board.generate_legal_moves(board.current_turn);
+    let legal_moves = state.timed(
+        |p| &mut p.movegen,
+        || {
+            let mut moves = board.generate_legal_moves(board.current_turn);
+            order_moves(board, &mut moves);
+            moves
+        },
+    );
     if legal_moves.is_empty() {
-        *evals += 1;
+        state.evals += 1;
         if board.is_in_check(board.current_turn) {
-            return -10000.0 - depth as f64;
+            return -(MATE_SCORE - ply as f64);
         }
         return 0.0;
     }
 
-    order_moves(board, &mut legal_moves);
-
     let mut best = f64::NEG_INFINITY;
-    for mv in &legal_moves {
-        let mut clone = board.clone();
-        clone.apply_move(mv);
-        let score = -negamax(&clone, depth - 1, -beta, -alpha, config, evals);
+    for (i, mv) in legal_moves.iter().enumerate() {
+        let clone = state.timed(|p| &mut p.make_move, || {
+            let mut clone = board.clone();
+            clone.apply_move(mv);
+            clone
+        });
+        let score = if config.pvs && i > 0 {
+            // Principal variation search: prove this move is no better than
+            // the best so far with a null window, and only re-search with the
+            // full window if it turns out to be.
+            let probe = -negamax(&clone, depth - 1, ply + 1, -alpha - PVS_EPSILON, -alpha, config, state);
+            if probe > alpha && probe < beta && !state.stopped {
+                -negamax(&clone, depth - 1, ply + 1, -beta, -alpha, config, state)
+            } else {
+                probe
+            }
+        } else {
+            -negamax(&clone, depth - 1, ply + 1, -beta, -alpha, config, state)
+        };
+        if state.stopped {
+            break;
+        }
         best = best.max(score);
         alpha = alpha.max(score);
         if alpha >= beta {
@@ -465,10 +1478,45 @@ fn negamax(
     best
 }
 
-// =============================================================================
-// Move selection
-// =============================================================================
-
+/// The raw alpha-beta search, for experiments that want their own depths
+/// and windows: `depth` plies from `board` within `(alpha, beta)`. The score
+/// is from the side to move's point of view, like `evaluate_pov` with
+/// `Pov::SideToMove`: exact inside the window, otherwise only a bound
+/// (≤ `alpha` or ≥ `beta`). The move is the best one found at this root,
+/// `None` at depth 0 or when there is nothing to play. Unlike `pick_move`
+/// there are no root safeguards or tie-breaks: the first of equally scored
+/// moves in search order wins.
+pub fn search(board: &Board, depth: u32, alpha: f64, beta: f64, config: &AiConfig) -> (f64, Option<Move>) {
+    let mut state = SearchState::new(config);
+    let mut moves = if depth == 0 || board.game_over { Vec::new() } else { board.generate_legal_moves(board.current_turn) };
+    if moves.is_empty() {
+        return (negamax(board, depth, 0, alpha, beta, config, &mut state), None);
+    }
+    order_moves(board, &mut moves);
+    let mut alpha = alpha;
+    let mut best = (f64::NEG_INFINITY, None);
+    for mv in moves {
+        let mut clone = board.clone();
+        clone.apply_move(&mv);
+        let score = -negamax(&clone, depth - 1, 1, -beta, -alpha, config, &mut state);
+        if state.stopped {
+            break;
+        }
+        if score > best.0 {
+            best = (score, Some(mv));
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+// =============================================================================
+// Move selection
+// =============================================================================
+
 /// Pick the best move for the current player.
 ///
 /// 1. Generate and order all legal moves
@@ -478,38 +1526,223 @@ fn negamax(
 ///
 /// Depth is specified in "full moves" (e.g. depth=2 means the AI looks 2 moves
 /// ahead for each side = 4 plies total). The first ply is consumed by applying
-/// each candidate move, so negamax is called with `plies - 1`.
-fn pick_move_at_depth(board: &Board, legal_moves: &[Move], plies: u32, config: &AiConfig) -> (Vec<ScoredMove>, u64) {
-    let mut evals: u64 = 0;
-    let scored: Vec<ScoredMove> = legal_moves
-        .iter()
-        .map(|mv| {
+/// each candidate move, so negamax is called with `plies - 1`. With zero
+/// plies each move is scored statically instead. With `stop_on_mate`, the
+/// remaining moves are skipped once one forces mate. If the search is
+/// interrupted, only the root moves that finished are returned.
+fn pick_move_at_depth(board: &Board, legal_moves: &[Move], plies: u32, config: &AiConfig, state: &mut SearchState) -> Vec<ScoredMove> {
+    if plies == 0 {
+        let scored: Vec<ScoredMove> = legal_moves
+            .iter()
+            .map(|mv| {
+                let mut clone = board.clone();
+                clone.apply_move(mv);
+                ScoredMove { mv: mv.clone(), score: evaluate(&clone, board.current_turn, config), nodes: 1 }
+            })
+            .collect();
+        state.evals += scored.len() as u64;
+        return scored;
+    }
+
+    let mut scored: Vec<ScoredMove> = Vec::with_capacity(legal_moves.len());
+    for mv in legal_moves {
+        let clone = state.timed(|p| &mut p.make_move, || {
             let mut clone = board.clone();
             clone.apply_move(mv);
-            let score = -negamax(&clone, plies - 1, f64::NEG_INFINITY, f64::INFINITY, config, &mut evals);
-            ScoredMove { mv: mv.clone(), score }
-        })
-        .collect();
-    (scored, evals)
+            clone
+        });
+        let before = state.evals;
+        let score = -negamax(&clone, plies - 1, 1, f64::NEG_INFINITY, f64::INFINITY, config, state);
+        if state.stopped {
+            break;
+        }
+        scored.push(ScoredMove { mv: mv.clone(), score, nodes: state.evals - before });
+        if config.stop_on_mate && score >= MATE_THRESHOLD {
+            break;
+        }
+    }
+    scored
 }
 
+/// Pick the best move, or `None` if the game cannot continue. Use
+/// `pick_move_or_reason` to find out why no move was returned.
 pub fn pick_move(board: &Board, config: &AiConfig) -> Option<PickResult> {
-    let mut legal_moves = board.generate_legal_moves(board.current_turn);
-    if legal_moves.is_empty() {
+    pick_move_or_reason(board, config).ok()
+}
+
+/// Like `pick_move`, but reports the rule that ended the game instead of
+/// returning `None`.
+pub fn pick_move_or_reason(board: &Board, config: &AiConfig) -> Result<PickResult, TerminalReason> {
+    if let Some(reason) = board.terminal_reason() {
+        return Err(reason);
+    }
+    if let Some(mv) = config.opening_book.then(|| book_move(board, config)).flatten() {
+        // Score the book move with the normal search, so the result stays
+        // comparable with out-of-book picks.
+        return Ok(search_root(board, config, vec![mv]));
+    }
+    let legal_moves = root_candidates(board, config, board.generate_legal_moves(board.current_turn));
+    let result = search_root(board, config, legal_moves.clone());
+    debug_assert!(
+        legal_moves.iter().any(|m| m.from == result.mv.from && m.to == result.mv.to && m.promotion == result.mv.promotion),
+        "pick_move chose an illegal move {}",
+        result.mv.to_uci()
+    );
+    Ok(result)
+}
+
+/// The book move to play for `config`, if `board` is in book: the most
+/// popular one, or a popularity-weighted draw with `book_variety`.
+fn book_move(board: &Board, config: &AiConfig) -> Option<Move> {
+    let moves = book_moves(board);
+    if !config.book_variety || config.deterministic {
+        return moves.into_iter().next().map(|(m, _)| m);
+    }
+    let total: u32 = moves.iter().map(|(_, w)| w).sum();
+    let mut pick = match config.seed {
+        Some(seed) => (splitmix64(seed ^ board.position_hash()).1 % total.max(1) as u64) as u32,
+        None => (random_f64() * total as f64) as u32,
+    };
+    for (mv, weight) in moves {
+        if pick < weight {
+            return Some(mv);
+        }
+        pick -= weight;
+    }
+    None
+}
+
+/// Like `pick_move`, but only the `allowed` moves are considered at the root
+/// (replies are still searched in full), for puzzles and constrained play.
+/// `None` if the game is over or none of `allowed` is legal.
+pub fn pick_move_restricted(board: &Board, config: &AiConfig, allowed: &[Move]) -> Option<PickResult> {
+    if board.terminal_reason().is_some() {
+        return None;
+    }
+    let candidates: Vec<Move> = board
+        .generate_legal_moves(board.current_turn)
+        .into_iter()
+        .filter(|m| allowed.iter().any(|a| a.from == m.from && a.to == m.to && a.promotion == m.promotion))
+        .collect();
+    if candidates.is_empty() {
         return None;
     }
+    Some(search_root(board, config, root_candidates(board, config, candidates)))
+}
+
+/// Search score of every legal move, from the mover's perspective and best
+/// first, for analysis overlays. This is `pick_move`'s root search without
+/// the choosing: every move is searched (no early stop on mate, no
+/// `no_early_queen_until` filter) and scores are not adjusted by the
+/// winning-side safeguards. With `max_nodes` or `time_budget_ms`, moves
+/// the budget did not reach are left out. Empty if the game is over.
+pub fn evaluate_all_moves(board: &Board, config: &AiConfig) -> Vec<(Move, f64)> {
+    if board.terminal_reason().is_some() {
+        return Vec::new();
+    }
+    let config = AiConfig { stop_on_mate: false, ..config.clone() };
+    let result = search_root(board, &config, board.generate_legal_moves(board.current_turn));
+    let mut scores: Vec<(Move, f64)> = result.root_stats.into_iter().map(|s| (s.mv, s.score)).collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| uci_order_key(&a.0).cmp(&uci_order_key(&b.0))));
+    scores
+}
+
+/// Apply the config's hard rules on which root moves may be played (see
+/// `no_early_queen_until`), keeping every move if none would be left.
+fn root_candidates(board: &Board, config: &AiConfig, moves: Vec<Move>) -> Vec<Move> {
+    if board.fullmove_number >= config.no_early_queen_until {
+        return moves;
+    }
+    let is_queen = |m: &Move| board.squares[m.from.0][m.from.1].is_some_and(|p| p.piece_type == PieceType::Queen);
+    if moves.iter().all(is_queen) {
+        return moves;
+    }
+    moves.into_iter().filter(|m| !is_queen(m)).collect()
+}
 
+/// Search the root `legal_moves` (non-empty) and choose among the best.
+fn search_root(board: &Board, config: &AiConfig, mut legal_moves: Vec<Move>) -> PickResult {
+    // Every node clones its parent, so start from a board without the
+    // unrepeatable part of the game's history.
+    let board = &board.clone_for_search();
     order_moves(board, &mut legal_moves);
 
-    let mut plies = config.depth * 2;
-    let (mut scored, mut evals) = pick_move_at_depth(board, &legal_moves, plies, config);
+    // A forced mate is good enough; deeper searches will not improve on it.
+    let found_mate = |scored: &[ScoredMove]| config.stop_on_mate && scored.iter().any(|s| s.score >= MATE_THRESHOLD);
+    let mut state = SearchState::new(config);
+    let search_start = state.profile.is_some().then(Instant::now);
+
+    let timed = config.time_budget_ms.is_some() && config.depth > 0;
+    let (mut scored, evals) = if config.max_nodes.is_some() || timed {
+        // Node or time budget: deepen until it runs out, keeping the last
+        // iteration that finished (or the finished root moves of the first).
+        // A time budget alone still stops at the configured depth.
+        let wants_deeper = |plies: u32, evals: u64| {
+            config.max_nodes.is_some()
+                || plies < config.depth * 2
+                || (config.auto_deepen && evals < config.min_evals && plies < 6)
+        };
+        let mut scored = Vec::new();
+        for plies in 1..=MAX_SEARCH_PLIES {
+            let iteration = pick_move_at_depth(board, &legal_moves, plies, config, &mut state);
+            if state.stopped {
+                if scored.is_empty() {
+                    scored = iteration;
+                }
+                break;
+            }
+            scored = iteration;
+            if found_mate(&scored) || !wants_deeper(plies, state.evals) {
+                break;
+            }
+        }
+        if scored.is_empty() {
+            // Budget too small for even one root move: score them statically.
+            scored = pick_move_at_depth(board, &legal_moves, 0, config, &mut state);
+        }
+        (scored, state.evals)
+    } else {
+        let mut plies = config.depth * 2;
+        let mut scored = pick_move_at_depth(board, &legal_moves, plies, config, &mut state);
+        let mut evals = state.evals;
+
+        // If auto-deepen is on and the search was too shallow, increase depth.
+        while config.auto_deepen && plies > 0 && evals < config.min_evals && plies < 6 && !found_mate(&scored) {
+            plies += 1;
+            let before = state.evals;
+            scored = pick_move_at_depth(board, &legal_moves, plies, config, &mut state);
+            evals = state.evals - before;
+        }
+        (scored, evals)
+    };
+
+    let root_stats: Vec<RootMoveStats> = scored
+        .iter()
+        .map(|s| RootMoveStats { mv: s.mv.clone(), nodes: s.nodes, score: s.score })
+        .collect();
 
-    // If auto-deepen is on and the search was too shallow, increase depth.
-    while config.auto_deepen && evals < config.min_evals && plies < 6 {
-        plies += 1;
-        let (new_scored, new_evals) = pick_move_at_depth(board, &legal_moves, plies, config);
-        scored = new_scored;
-        evals = new_evals;
+    // Safeguard: shallow search can wander into stalemating a lone king,
+    // shuffle into a repetition, or drift towards the fifty-move draw while
+    // converting. When clearly winning, push stalemating moves below the
+    // alternatives and prefer progress over near-equal repeats and stalls.
+    if material_lead(board, board.current_turn) >= WINNING_MATERIAL_LEAD {
+        for s in scored.iter_mut() {
+            let mut clone = board.clone();
+            clone.apply_move(&s.mv);
+            if clone.is_stalemate() {
+                s.score -= STALEMATE_PENALTY;
+            } else if clone.is_repetition() {
+                // The search's draw contempt (draw_contempt) is kept small;
+                // when clearly winning, make progress unless repeating is
+                // clearly better.
+                s.score -= REPETITION_MARGIN;
+            }
+            // A capture or pawn move resets the clock; anything else stalls.
+            let left = clone.moves_until_fifty_move_draw();
+            if clone.halfmove_clock > 0 && left < STALL_WARNING_HALFMOVES {
+                s.score -= STALL_MARGIN * (STALL_WARNING_HALFMOVES - left) as f64 / STALL_WARNING_HALFMOVES as f64;
+            }
+        }
     }
 
     let max_score = scored
@@ -517,18 +1750,319 @@ pub fn pick_move(board: &Board, config: &AiConfig) -> Option<PickResult> {
         .map(|s| s.score)
         .fold(f64::NEG_INFINITY, f64::max);
 
-    let best: Vec<&ScoredMove> = scored
+    // The hang check and king-pressure tie-break widen the ties to
+    // near-equal moves; the filters below then narrow whatever candidate set
+    // they are handed.
+    let mut tie_margin: f64 = 0.001;
+    if config.hang_check {
+        tie_margin = tie_margin.max(HANG_CHECK_MARGIN);
+    }
+    if config.king_pressure_tiebreak {
+        tie_margin = tie_margin.max(KING_PRESSURE_MARGIN);
+    }
+    let mut best: Vec<&ScoredMove> = scored
         .iter()
-        .filter(|s| (s.score - max_score).abs() < 0.001)
+        .filter(|s| s.score >= max_score - tie_margin)
         .collect();
 
+    if config.solid_play {
+        let them = board.current_turn.opposite();
+        let risk = |mv: &Move| {
+            let mut child = board.clone();
+            child.apply_move(mv);
+            child.forcing_moves(them).len()
+        };
+        let sound: Vec<(&ScoredMove, usize)> = scored
+            .iter()
+            .filter(|s| s.score >= max_score - SOLID_MARGIN)
+            .map(|s| (s, risk(&s.mv)))
+            .collect();
+        if let Some(least) = sound.iter().map(|&(_, r)| r).min() {
+            best = sound.into_iter().filter(|&(_, r)| r == least).map(|(s, _)| s).collect();
+        }
+    }
+
+    if config.hang_check && best.len() > 1 {
+        best.sort_by(|a, b| b.score.total_cmp(&a.score));
+        best.truncate(HANG_CHECK_CANDIDATES);
+        let demoted: Vec<(&ScoredMove, f64)> =
+            best.iter().map(|&s| (s, s.score - best_reply_capture(board, &s.mv))).collect();
+        let top = demoted.iter().map(|&(_, d)| d).fold(f64::NEG_INFINITY, f64::max);
+        best = demoted.into_iter().filter(|&(_, d)| d >= top - 0.001).map(|(s, _)| s).collect();
+    }
+
+    if config.king_pressure_tiebreak {
+        let us = board.current_turn;
+        let pressure = |mv: &Move| {
+            let mut child = board.clone();
+            child.apply_move(mv);
+            king_zone_attacks(&child, us) as i64 - king_zone_attacks(&child, us.opposite()) as i64
+        };
+        let with_pressure: Vec<(&ScoredMove, i64)> = best.iter().map(|&s| (s, pressure(&s.mv))).collect();
+        if let Some(most) = with_pressure.iter().map(|&(_, p)| p).max() {
+            best = with_pressure.into_iter().filter(|&(_, p)| p == most).map(|(s, _)| s).collect();
+        }
+    }
+
     // Among tied moves, prefer the highest-priority one (e.g. queen promotion
     // over bishop promotion) then randomize among any still tied.
     let max_pri = best.iter().map(|s| move_priority(board, &s.mv)).max().unwrap_or(0);
     let top: Vec<&ScoredMove> = best.into_iter().filter(|s| move_priority(board, &s.mv) == max_pri).collect();
 
-    let index = (random_f64() * top.len() as f64) as usize;
-    Some(PickResult { mv: top[index.min(top.len() - 1)].mv.clone(), evals })
+    let chosen = if top.is_empty() {
+        None
+    } else if config.deterministic {
+        top.into_iter().min_by_key(|s| uci_order_key(&s.mv))
+    } else {
+        Some(top[tie_break_index(board, config, top.len())])
+    };
+    // Last resort, should the search come back empty-handed (say, every
+    // score NaN): play the first ordered move rather than no move at all.
+    let (mv, score) = match chosen {
+        Some(s) => (s.mv.clone(), s.score),
+        None => {
+            let mv = legal_moves[0].clone();
+            let mut child = board.clone();
+            child.apply_move(&mv);
+            (mv, -evaluate_pov(&child, Pov::SideToMove, config))
+        }
+    };
+    let profile = state.profile.take().map(|p| SearchProfile { total: search_start.map_or(Duration::ZERO, |t| t.elapsed()), ..p });
+    PickResult { mv, score, evals, root_stats, profile }
+}
+
+// =============================================================================
+// Display
+// =============================================================================
+
+/// A score mapped for display: normal evaluations are clamped to ±10 pawns
+/// so eval bars stay readable, and mates are reported by distance instead
+/// of as huge numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayEval {
+    /// Clamped score in pawns (±10 for a mate).
+    pub pawns: f64,
+    /// Full moves to mate: positive if the scoring side mates, negative if
+    /// it is mated, 0 if the position is already checkmate.
+    pub mate_in: Option<i32>,
+    /// "+1.5", "M3", "-M2", or "#" / "-#" for a finished mate.
+    pub text: String,
+}
+
+/// Map a search or evaluation score (any perspective) for display.
+pub fn display_eval(score: f64) -> DisplayEval {
+    if score.abs() >= MATE_THRESHOLD {
+        let plies = (MATE_SCORE - score.abs()).round().max(0.0) as i32;
+        let moves = (plies + 1) / 2;
+        let sign = if score > 0.0 { "" } else { "-" };
+        let text = if moves == 0 { format!("{sign}#") } else { format!("{sign}M{moves}") };
+        return DisplayEval {
+            pawns: DISPLAY_EVAL_CAP.copysign(score),
+            mate_in: Some(if score > 0.0 { moves } else { -moves }),
+            text,
+        };
+    }
+    let pawns = score.clamp(-DISPLAY_EVAL_CAP, DISPLAY_EVAL_CAP);
+    DisplayEval { pawns, mate_in: None, text: format!("{pawns:+.1}") }
+}
+
+// =============================================================================
+// Self-play
+// =============================================================================
+
+/// A game from `self_play`: the moves played and the final position.
+pub struct SelfPlayGame {
+    pub moves: Vec<Move>,
+    pub board: Board,
+}
+
+/// Play a game from the start position with each side searching under its
+/// own config, stopping when the game ends, a side has no move to play, or
+/// after `max_plies` plies (check `board.game_over` to tell them apart).
+///
+/// `evaluate` only ever sees the config of the side that is searching, so
+/// the two sides may use entirely different modules, depths and weights.
+pub fn self_play(white: &AiConfig, black: &AiConfig, max_plies: usize) -> SelfPlayGame {
+    let mut board = Board::new();
+    let mut moves = Vec::new();
+    while !board.game_over && moves.len() < max_plies {
+        let config = if board.current_turn == Color::White { white } else { black };
+        let Ok(result) = pick_move_or_reason(&board, config) else {
+            break;
+        };
+        board.apply_move(&result.mv);
+        moves.push(result.mv);
+    }
+    SelfPlayGame { moves, board }
+}
+
+/// `self_play` with `config` for both sides except for the evaluation
+/// weights, so a personality matchup (say, a materialist against a
+/// centre-hungry positional player) is a one-liner.
+pub fn play_weighted(white_weights: &Weights, black_weights: &Weights, config: &AiConfig, max_plies: usize) -> SelfPlayGame {
+    let white = AiConfig { weights: white_weights.clone(), ..config.clone() };
+    let black = AiConfig { weights: black_weights.clone(), ..config.clone() };
+    self_play(&white, &black, max_plies)
+}
+
+// =============================================================================
+// Game analysis
+// =============================================================================
+
+/// Replay `moves` from the start position of `variant` and search each
+/// position before the move is played, recording the engine's best move,
+/// its score and the score of the move actually played. Every legal move is
+/// scored as in `evaluate_all_moves`, so a played move outside the opening
+/// book, `no_early_queen_until` or the first mate found is still judged.
+/// Stops early at an illegal move or once the game is over; a ply whose
+/// played move the search budget did not reach is left out.
+pub fn analyze_game(variant: Variant, moves: &[Move], config: &AiConfig) -> Vec<AnalysisPoint> {
+    let mut board = Board::new();
+    board.variant = variant;
+    let mut points = Vec::new();
+    for (ply, played) in moves.iter().enumerate() {
+        if !board.is_legal(played) {
+            break;
+        }
+        let scores = evaluate_all_moves(&board, config);
+        let same = |m: &Move| m.from == played.from && m.to == played.to && m.promotion == played.promotion;
+        if let (Some((best, best_score)), Some((_, played_score))) = (scores.first(), scores.iter().find(|(m, _)| same(m))) {
+            points.push(AnalysisPoint {
+                ply,
+                best: best.clone(),
+                best_score: *best_score,
+                played: played.clone(),
+                played_score: *played_score,
+                blunder: best_score - played_score >= BLUNDER_THRESHOLD,
+            });
+        }
+        board.apply_move(played);
+    }
+    points
+}
+
+// =============================================================================
+// Move explanation
+// =============================================================================
+
+fn piece_name(pt: PieceType) -> &'static str {
+    match pt {
+        PieceType::King => "king",
+        PieceType::Queen => "queen",
+        PieceType::Rook => "rook",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Pawn => "pawn",
+    }
+}
+
+/// Pick a move with `pick_move` and explain it in a sentence (see
+/// `describe_move`). Says why not if there is no move to play.
+pub fn describe_choice(board: &Board, config: &AiConfig) -> String {
+    match pick_move_or_reason(board, config) {
+        Ok(result) => describe_move(board, &result.mv, config),
+        Err(reason) => format!("No move to play: {reason:?}."),
+    }
+}
+
+/// A short, rule-based rationale for playing `mv`, e.g. "Develops a knight
+/// toward the centre and attacks the pawn on e5." Built from the material
+/// the move wins (by static exchange), checks, threats and defences, and
+/// falls back to the largest gain in the eval breakdown.
+pub fn describe_move(board: &Board, mv: &Move, config: &AiConfig) -> String {
+    let Some(piece) = board.squares[mv.from.0][mv.from.1] else {
+        return "Plays an illegal move.".to_string();
+    };
+    let us = piece.color;
+    let them = us.opposite();
+    let mut after = board.clone();
+    after.apply_move(mv);
+    let mut clauses: Vec<String> = Vec::new();
+
+    if after.winner() == Some(us) {
+        return "Delivers checkmate.".to_string();
+    }
+
+    if board.is_castle(mv) {
+        clauses.push("castles the king to safety".to_string());
+    } else if board.is_capture(mv) {
+        let victim = board.squares[mv.to.0][mv.to.1].map_or(PieceType::Pawn, |p| p.piece_type);
+        // What we lose back if the capturing piece can then be won.
+        let lost = after.see(mv.to.0, mv.to.1, them).map_or(0.0, |g| g.max(0.0));
+        let net = victim.value() - lost;
+        clauses.push(if net >= victim.value() {
+            format!("wins a {}", piece_name(victim))
+        } else if net > 0.0 {
+            "wins material".to_string()
+        } else if net == 0.0 {
+            format!("trades for a {}", piece_name(victim))
+        } else {
+            format!("gives up material to capture a {}", piece_name(victim))
+        });
+    } else if let Some(promo) = mv.promotion {
+        clauses.push(format!("promotes to a {}", piece_name(promo)));
+    } else if matches!(piece.piece_type, PieceType::Knight | PieceType::Bishop)
+        && mv.from.0 == if us == Color::White { 0 } else { 7 }
+    {
+        let toward = if EXTENDED_CENTRE.contains(&mv.to) || CENTRE_SQUARES.contains(&mv.to) { " toward the centre" } else { "" };
+        clauses.push(format!("develops a {}{toward}", piece_name(piece.piece_type)));
+    }
+
+    if after.is_in_check(them) {
+        clauses.push("gives check".to_string());
+    }
+
+    // Threats: enemy pieces the moved piece now attacks and could win.
+    let hanging_after = after.hanging_pieces(them);
+    let threatened: Vec<(usize, usize)> = after
+        .attacks_from(mv.to.0, mv.to.1)
+        .into_iter()
+        .filter(|sq| hanging_after.contains(sq) && !board.hanging_pieces(them).contains(sq))
+        .collect();
+    if let Some(&(r, c)) = threatened.first() {
+        let target = after.squares[r][c].map_or(PieceType::Pawn, |p| p.piece_type);
+        clauses.push(format!("attacks the {} on {}", piece_name(target), index_to_square((r, c))));
+    }
+
+    // Defences: our pieces that were en prise and no longer are.
+    let hanging_before = board.hanging_pieces(us);
+    let hanging_now = after.hanging_pieces(us);
+    if hanging_before.iter().any(|sq| !hanging_now.contains(sq)) && hanging_now.len() < hanging_before.len() {
+        if hanging_before.contains(&mv.from) {
+            clauses.push(format!("moves the {} out of danger", piece_name(piece.piece_type)));
+        } else {
+            clauses.push("defends a threatened piece".to_string());
+        }
+    }
+
+    if clauses.is_empty() {
+        let b = evaluate_breakdown(board, us, config);
+        let a = evaluate_breakdown(&after, us, config);
+        let gains = [
+            (a.centre - b.centre, "improves control of the centre"),
+            (a.passed_pawns - b.passed_pawns, "advances a passed pawn"),
+            (a.outposts - b.outposts, "takes up an outpost"),
+            (a.rooks - b.rooks, "coordinates the rooks"),
+            (a.king_attack - b.king_attack, "builds up an attack on the king"),
+            (a.king_safety - b.king_safety, "improves king safety"),
+            (a.space - b.space, "gains space"),
+            (a.development - b.development, "develops a piece"),
+        ];
+        match gains.iter().filter(|(gain, _)| *gain > 0.0).max_by(|x, y| x.0.total_cmp(&y.0)) {
+            Some((_, text)) => clauses.push(text.to_string()),
+            None => clauses.push("makes a quiet waiting move".to_string()),
+        }
+    }
+
+    let mut text = match clauses.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => unreachable!("at least one clause"),
+    };
+    if let Some(first) = text.get(..1) {
+        text = first.to_uppercase() + &text[1..];
+    }
+    text + "."
 }
 
 #[cfg(test)]
@@ -536,6 +2070,22 @@ mod tests {
     use super::*;
     use crate::piece::Piece;
 
+    /// A fixed-depth, deterministic config that scores material alone, so
+    /// the root tie-breaks decide between quiet moves.
+    fn material_only_config(depth: u32) -> AiConfig {
+        let mut config = AiConfig::new();
+        config.mate_module = false;
+        config.centre_module = false;
+        config.passed_pawn_module = false;
+        config.outpost_module = false;
+        config.rook_module = false;
+        config.draw_penalty_module = false;
+        config.depth = depth;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        config
+    }
+
     /// Place kings + a white pawn on a7, with pawns to anchor the kings.
     /// The engine must promote to queen.
     fn board_pawn_on_a7() -> Board {
@@ -627,6 +2177,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn points_of_view_differ_by_sign() {
+        // White is a pawn up, Black to move.
+        let board = Board::from_uci_moves(&["g1f3", "e7e5", "f3e5", "a7a6", "e5f3"]).unwrap();
+        assert_eq!(board.current_turn, Color::Black);
+
+        let config = AiConfig::new();
+        let white = evaluate_pov(&board, Pov::White, &config);
+        let black = evaluate_pov(&board, Pov::Color(Color::Black), &config);
+        let to_move = evaluate_pov(&board, Pov::SideToMove, &config);
+        assert!(white > 0.5, "White is a pawn up: {white}");
+        assert!((white + black).abs() < 1e-9);
+        assert!((to_move - black).abs() < 1e-9);
+        assert!((evaluate_pov(&board, Pov::Color(Color::White), &config) - white).abs() < 1e-9);
+    }
+
     #[test]
     fn draw_penalty_applies_on_repeat() {
         let mut board = Board::empty();
@@ -658,6 +2224,253 @@ mod tests {
         assert!(result.evals > 0, "should have evaluated at least one position");
     }
 
+    #[test]
+    fn profile_accounts_for_search_time() {
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        assert!(pick_move(&Board::new(), &config).unwrap().profile.is_none());
+
+        config.profile = true;
+        let p = pick_move(&Board::new(), &config).unwrap().profile.expect("profiling was requested");
+        assert!(p.movegen > Duration::ZERO && p.eval > Duration::ZERO && p.make_move > Duration::ZERO, "{p:?}");
+        let parts = p.movegen + p.eval + p.make_move;
+        assert!(parts <= p.total, "{p:?}");
+        assert!(parts >= p.total / 2, "parts should cover most of the search: {p:?}");
+    }
+
+    #[test]
+    fn leaf_in_check_is_searched_not_evaluated() {
+        // Black's queen has just given check on f2, where the king takes it.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[1][5] = Some(Piece::new(PieceType::Queen, Color::Black));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[1][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.position_history.push(board.position_hash());
+
+        let mut config = AiConfig::new();
+        let mut state = SearchState::new(&config);
+        let extended = negamax(&board, 0, 2, f64::NEG_INFINITY, f64::INFINITY, &config, &mut state);
+        assert!(extended > 0.0, "Kxf2 should be found at the leaf, got {extended}");
+
+        config.leaf_check_extension = false;
+        let mut state = SearchState::new(&config);
+        let static_eval = negamax(&board, 0, 2, f64::NEG_INFINITY, f64::INFINITY, &config, &mut state);
+        assert!(static_eval < -5.0, "static eval sees Black a queen up, got {static_eval}");
+    }
+
+    #[test]
+    fn describes_a_winning_capture() {
+        // Black's queen on h4 is en prise to the f3 knight.
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "d8h4"]).unwrap();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let text = describe_choice(&board, &config);
+        assert!(text.starts_with("Wins a queen"), "{text}");
+
+        let develop = describe_move(&Board::new(), &Move::from_uci("g1f3").unwrap(), &config);
+        assert_eq!(develop, "Develops a knight toward the centre.");
+    }
+
+    #[test]
+    fn live_position_always_gets_a_legal_move() {
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3"]).unwrap();
+        let legal: Vec<String> = board.generate_legal_moves(Color::Black).iter().map(Move::to_uci).collect();
+        for max_nodes in [0, 1, 10] {
+            let mut config = AiConfig::new();
+            config.max_nodes = Some(max_nodes);
+            let result = pick_move(&board, &config).expect("legal moves exist");
+            assert!(legal.contains(&result.mv.to_uci()), "{max_nodes} nodes: {}", result.mv.to_uci());
+        }
+
+        // Even a search whose scores are all NaN still plays a move.
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.weights.centre_attack = f64::NAN;
+        let result = pick_move(&board, &config).expect("legal moves exist");
+        assert!(legal.contains(&result.mv.to_uci()));
+    }
+
+    #[test]
+    fn restricted_pick_only_plays_allowed_moves() {
+        let board = Board::new();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let only = [Move::from_uci("a2a3").unwrap()];
+        let result = pick_move_restricted(&board, &config, &only).unwrap();
+        assert_eq!(result.mv.to_uci(), "a2a3");
+        assert_eq!(result.root_stats.len(), 1);
+
+        let knights: Vec<Move> = ["b1a3", "b1c3", "g1f3", "g1h3"].iter().map(|m| Move::from_uci(m).unwrap()).collect();
+        let result = pick_move_restricted(&board, &config, &knights).unwrap();
+        assert!(knights.iter().any(|k| k.to_uci() == result.mv.to_uci()));
+
+        assert!(pick_move_restricted(&board, &config, &[]).is_none());
+        assert!(pick_move_restricted(&board, &config, &[Move::from_uci("e2e5").unwrap()]).is_none());
+    }
+
+    #[test]
+    fn evaluate_all_moves_scores_every_move_and_agrees_with_pick_move() {
+        // Italian opening, Black to move: not a winning position, so
+        // pick_move's safeguards leave the scores alone.
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4"]).unwrap();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.seed = Some(7);
+        let scores = evaluate_all_moves(&board, &config);
+        assert_eq!(scores.len(), board.legal_move_count(board.current_turn));
+        assert!(scores.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let pick = pick_move(&board, &config).unwrap();
+        assert!((pick.score - scores[0].1).abs() < 0.001);
+        let tied: Vec<String> = scores.iter().filter(|(_, s)| (s - scores[0].1).abs() < 0.001).map(|(m, _)| m.to_uci()).collect();
+        assert!(tied.contains(&pick.mv.to_uci()), "{} not among {tied:?}", pick.mv.to_uci());
+
+        // Finding a mate does not cut the list short.
+        let mate = Board::from_uci_moves(&["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6"]).unwrap();
+        let scores = evaluate_all_moves(&mate, &config);
+        assert_eq!(scores[0].0.to_uci(), "h5f7");
+        assert!(scores[0].1 >= MATE_THRESHOLD);
+        assert_eq!(scores.len(), mate.legal_move_count(mate.current_turn));
+        assert!(evaluate_all_moves(&Board::from_fen(crate::testpositions::STALEMATE).unwrap(), &config).is_empty());
+    }
+
+    #[test]
+    fn finds_perpetual_check_when_losing() {
+        // Black threatens ...Rb1#; White's only defence is perpetual check
+        // with Qe8+ Kh7 Qh5+ Kg8.
+        let mut board = Board::empty();
+        board.squares[0][7] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[6][3] = Some(Piece::new(PieceType::Queen, Color::White));
+        board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        for col in 0..3 {
+            board.squares[6][col] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        }
+        board.squares[1][0] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[1][1] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.position_history.push(board.position_hash());
+        // Play the cycle once so the search can see it repeat.
+        for uci in ["d7e8", "g8h7", "e8h5", "h7g8"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+
+        let mut config = AiConfig::new();
+        config.depth = 2;
+        config.auto_deepen = false;
+        let result = pick_move(&board, &config).unwrap();
+        assert_eq!(result.mv.to_uci(), "h5e8");
+        let draw = -config.weights.draw_contempt;
+        assert!((result.score - draw).abs() < 1e-9, "perpetual should score as a draw ({draw}), got {}", result.score);
+        config.draw_penalty_module = false;
+        let result = pick_move(&board, &config).unwrap();
+        assert_eq!(result.mv.to_uci(), "h5e8");
+        assert!(result.score.abs() < 1e-9, "without contempt a draw is 0, got {}", result.score);
+    }
+
+    #[test]
+    fn pvs_matches_plain_alpha_beta() {
+        let positions = [
+            Board::new(),
+            Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"]).unwrap(),
+            Board::from_uci_moves(&["d2d4", "d7d5", "c2c4", "d5c4", "e2e4"]).unwrap(),
+            Board::from_uci_moves(&["e2e4", "f7f6", "d2d4", "g7g5"]).unwrap(), // Qh5# available
+        ];
+        for board in &positions {
+            let mut config = AiConfig::new();
+            config.depth = 1;
+            config.auto_deepen = false;
+            config.deterministic = true;
+            config.stop_on_mate = false;
+            let pvs = pick_move(board, &config).unwrap();
+            config.pvs = false;
+            let plain = pick_move(board, &config).unwrap();
+            assert_eq!(pvs.mv.to_uci(), plain.mv.to_uci());
+            assert!((pvs.score - plain.score).abs() < 1e-9, "{} vs {}", pvs.score, plain.score);
+            // Root moves get a full window either way, so every score is exact.
+            for (a, b) in pvs.root_stats.iter().zip(&plain.root_stats) {
+                assert!((a.score - b.score).abs() < 1e-9, "{}: {} vs {}", a.mv.to_uci(), a.score, b.score);
+            }
+        }
+    }
+
+    #[test]
+    fn time_budget_returns_on_time_with_a_legal_move() {
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"]).unwrap();
+        let mut config = AiConfig::new();
+        config.depth = 3;
+        config.auto_deepen = false;
+        config.time_budget_ms = Some(100);
+        let start = Instant::now();
+        let result = pick_move(&board, &config).unwrap();
+        let elapsed = start.elapsed().as_millis();
+        // Generous slack for slow debug builds.
+        assert!(elapsed < 1_000, "took {elapsed} ms on a 100 ms budget");
+        assert!(board.is_legal(&result.mv), "{}", result.mv.to_uci());
+
+        // Given ample time, the budget doesn't change a shallow search.
+        config.depth = 1;
+        config.deterministic = true;
+        config.time_budget_ms = Some(60_000);
+        let timed = pick_move(&board, &config).unwrap();
+        config.time_budget_ms = None;
+        assert_eq!(timed.mv.to_uci(), pick_move(&board, &config).unwrap().mv.to_uci());
+    }
+
+    #[test]
+    fn max_nodes_stops_at_budget() {
+        let board = Board::new();
+        let mut config = AiConfig::new();
+        config.max_nodes = Some(5_000);
+        let result = pick_move(&board, &config).expect("should find a move");
+        assert!(result.evals >= 5_000, "budget should be used up, got {}", result.evals);
+        assert!(result.evals <= 5_001, "search overran the budget: {}", result.evals);
+        assert!(!result.root_stats.is_empty());
+
+        // A budget too small for any root move still returns a move.
+        config.max_nodes = Some(0);
+        assert!(pick_move(&board, &config).is_some());
+    }
+
+    #[test]
+    fn stop_on_mate_skips_remaining_root_moves() {
+        // Back-rank mate by capture, so it is searched first: Rxd8#.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][5] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[7][3] = Some(Piece::new(PieceType::Knight, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        assert!(!config.stop_on_mate, "opt-in");
+        let full = pick_move(&board, &config).unwrap();
+        assert_eq!(full.root_stats.len(), board.legal_move_count(Color::White));
+        config.stop_on_mate = true;
+        let stopped = pick_move(&board, &config).unwrap();
+
+        assert_eq!(stopped.mv.to, (7, 3), "should still play Rxd8#");
+        assert!(stopped.root_stats.len() < full.root_stats.len());
+        assert!(
+            stopped.evals * 2 < full.evals,
+            "stopping at mate should save most of the search ({} vs {})",
+            stopped.evals,
+            full.evals
+        );
+    }
+
     #[test]
     fn finds_mate_in_one() {
         // White: Ke1, Rd1. Black: Kg8, pawns f7 g7 h7.
@@ -699,4 +2512,907 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pick_move_reports_terminal_reason() {
+        // Back-rank mate: White Kg1 behind f2 g2 h2, Black Ra1.
+        let mut mated = Board::empty();
+        mated.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        mated.squares[1][5] = Some(Piece::new(PieceType::Pawn, Color::White));
+        mated.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        mated.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        mated.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::Black));
+        mated.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        mated.current_turn = Color::White;
+
+        // White Ka1, Black Qb3 + Kc2: stalemate.
+        let mut stalemated = Board::empty();
+        stalemated.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        stalemated.squares[2][1] = Some(Piece::new(PieceType::Queen, Color::Black));
+        stalemated.squares[1][2] = Some(Piece::new(PieceType::King, Color::Black));
+        stalemated.current_turn = Color::White;
+
+        let config = AiConfig::new();
+        assert_eq!(pick_move_or_reason(&mated, &config).err(), Some(TerminalReason::Checkmate));
+        assert_eq!(pick_move_or_reason(&stalemated, &config).err(), Some(TerminalReason::Stalemate));
+        assert!(pick_move(&mated, &config).is_none());
+    }
+
+    #[test]
+    fn fifty_move_fen_loads_as_a_finished_game() {
+        // Legal moves remain, but the clock has already run out: the AI and
+        // a human are refused alike.
+        let board = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80").unwrap();
+        assert!(board.legal_move_count(Color::White) > 0);
+        assert!(board.game_over);
+        assert_eq!(board.result.as_deref(), Some("Draw — 50 move rule"));
+        assert_eq!(pick_move_or_reason(&board, &AiConfig::new()).err(), Some(TerminalReason::FiftyMoveRule));
+        assert!(!board.is_legal(&Move::from_uci("e2e4").unwrap()));
+
+        let live = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+        assert!(!live.game_over);
+        assert!(pick_move(&live, &AiConfig::new()).is_some());
+    }
+
+    #[test]
+    fn king_attack_rewards_pieces_near_enemy_king() {
+        // Black Kg8 behind f7 g7 h7; White has a queen, rook and knight.
+        let base = || {
+            let mut board = Board::empty();
+            board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+            board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+            board.squares[6][5] = Some(Piece::new(PieceType::Pawn, Color::Black));
+            board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+            board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+            board
+        };
+        let mut attacking = base();
+        attacking.squares[4][7] = Some(Piece::new(PieceType::Queen, Color::White));  // Qh5
+        attacking.squares[4][6] = Some(Piece::new(PieceType::Knight, Color::White)); // Ng5
+        attacking.squares[2][5] = Some(Piece::new(PieceType::Rook, Color::White));   // Rf3
+        let mut quiet = base();
+        quiet.squares[0][0] = Some(Piece::new(PieceType::Queen, Color::White));      // Qa1
+        quiet.squares[0][1] = Some(Piece::new(PieceType::Knight, Color::White));     // Nb1
+        quiet.squares[1][0] = Some(Piece::new(PieceType::Rook, Color::White));       // Ra2
+
+        let mut config = AiConfig::new();
+        config.centre_module = false;
+        config.attack_module = true;
+        let attack_score = evaluate(&attacking, Color::White, &config);
+        let quiet_score = evaluate(&quiet, Color::White, &config);
+        assert!(attack_score > quiet_score,
+            "attacking setup ({attack_score}) should beat the quiet one ({quiet_score})");
+        assert!(evaluate_breakdown(&attacking, Color::White, &config).king_attack > 0.0);
+    }
+
+    #[test]
+    fn seeded_pick_move_is_repeatable() {
+        let board = Board::new();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.seed = Some(42);
+        let first = pick_move(&board, &config).expect("should find a move");
+        for _ in 0..5 {
+            let again = pick_move(&board, &config).expect("should find a move");
+            assert_eq!(again.mv.to_uci(), first.mv.to_uci());
+            assert_eq!(again.score, first.score);
+        }
+    }
+
+    #[test]
+    fn premature_shield_pawn_push_scores_worse() {
+        // Both sides castled kingside with their f/g/h pawns at home.
+        let castled = Board::from_uci_moves(&["g1f3", "g8f6", "e2e3", "e7e6", "f1e2", "f8e7", "e1g1", "e8g8"]).unwrap();
+        let mut pushed = castled.clone();
+        pushed.apply_move(&Move::from_uci("g2g4").unwrap());
+        let mut developed = castled.clone();
+        developed.apply_move(&Move::from_uci("b1c3").unwrap());
+
+        let mut config = AiConfig::new();
+        config.centre_module = false;
+        config.passed_pawn_module = false;
+        config.king_safety_module = true;
+        assert!(evaluate(&pushed, Color::White, &config) < evaluate(&developed, Color::White, &config));
+        assert!(evaluate_breakdown(&pushed, Color::White, &config).king_safety < 0.0);
+        assert_eq!(evaluate_breakdown(&developed, Color::White, &config).king_safety, 0.0);
+        assert_eq!(game_phase(&Board::new()), 1.0);
+
+        // Down to rooks, the pushed pawn no longer matters.
+        let mut endgame = pushed.clone();
+        for (row, col) in (0..8).flat_map(|r| (0..8).map(move |c| (r, c))) {
+            let sq = endgame.squares[row][col];
+            if sq.is_some_and(|p| matches!(p.piece_type, PieceType::Queen | PieceType::Bishop | PieceType::Knight)) {
+                endgame.set_square(row, col, None);
+            }
+        }
+        assert!(endgame.is_endgame());
+        assert_eq!(evaluate_breakdown(&endgame, Color::White, &config).king_safety, 0.0);
+    }
+
+    #[test]
+    fn display_eval_clamps_and_reports_mates() {
+        let normal = display_eval(1.53);
+        assert_eq!(normal.text, "+1.5");
+        assert_eq!(normal.mate_in, None);
+        assert_eq!(display_eval(-42.0).pawns, -10.0);
+
+        // Mate in 3 = mate on the 5th ply from the root.
+        let mate = display_eval(MATE_SCORE - 5.0);
+        assert_eq!(mate.text, "M3");
+        assert_eq!(mate.mate_in, Some(3));
+        assert_eq!(mate.pawns, 10.0);
+        assert_eq!(display_eval(-(MATE_SCORE - 4.0)).text, "-M2");
+    }
+
+    #[test]
+    fn search_scores_mate_in_one_by_distance() {
+        // White: Ke1, Rd1. Black: Kg8, pawns f7 g7 h7. Rd8# is mate in 1.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][5] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut config = AiConfig::new();
+        config.depth = 2;
+        config.auto_deepen = false;
+        let result = pick_move(&board, &config).unwrap();
+        assert_eq!(display_eval(result.score).text, "M1");
+    }
+
+    #[test]
+    fn raw_search_agrees_with_pick_move() {
+        // 1.e4 e5 2.Nf3 Qh4?? — the knight takes the queen.
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "d8h4"]).unwrap();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        let picked = pick_move(&board, &config).unwrap();
+        let (score, mv) = search(&board, 2, f64::NEG_INFINITY, f64::INFINITY, &config);
+        assert_eq!(mv.unwrap().to_uci(), picked.mv.to_uci());
+        assert_eq!(picked.mv.to_uci(), "f3h4");
+        assert!((score - picked.score).abs() < 1e-9, "search {score} vs pick_move {}", picked.score);
+
+        // A null window above the true score fails low; depth 0 is the
+        // static evaluation with no move.
+        assert!(search(&board, 2, score + 1.0, score + 1.0 + 1e-6, &config).0 <= score + 1.0);
+        let (static_score, none) = search(&board, 0, f64::NEG_INFINITY, f64::INFINITY, &config);
+        assert_eq!(static_score, evaluate_pov(&board, Pov::SideToMove, &config));
+        assert!(none.is_none());
+    }
+
+    #[test]
+    fn development_prefers_minor_pieces_to_rook_pawns() {
+        let mut config = AiConfig::new();
+        config.development_module = true;
+        let after = |uci: &str| Board::from_uci_moves(&[uci]).unwrap();
+        let development = |b: &Board| evaluate_breakdown(b, Color::White, &config).development;
+        assert_eq!(development(&Board::new()), 0.0);
+        assert!(development(&after("g1f3")) > development(&after("a2a4")));
+        assert!(development(&after("b1c3")) > development(&after("h2h4")));
+        // An early queen sortie doubles the lag of the minors left at home.
+        assert!(development(&after("e2e3")) > development(&Board::from_uci_moves(&["e2e3", "e7e6", "d1h5"]).unwrap()));
+
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        let result = pick_move(&Board::new(), &config).unwrap();
+        assert!(![0, 7].contains(&result.mv.from.1), "pushed a rook pawn: {}", result.mv.to_uci());
+        let best_of = |moves: &[&str]| {
+            result.root_stats.iter().filter(|s| moves.contains(&s.mv.to_uci().as_str())).map(|s| s.score).fold(f64::NEG_INFINITY, f64::max)
+        };
+        assert!(best_of(&["g1f3", "b1c3"]) > best_of(&["a2a3", "a2a4", "h2h3", "h2h4"]));
+    }
+
+    #[test]
+    fn solid_play_declines_the_sharp_sacrifice() {
+        // Material-only and static, so every knight move scores the same.
+        // Nf6+ is the natural pick (it eyes the extended centre) but hands
+        // Black gxf6; Nf2 and Nh2 leave Black nothing forcing.
+        let mut board = Board::empty();
+        board.squares[2][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][6] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[4][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[5][4] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+
+        let mut config = material_only_config(0);
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "g4f6");
+
+        config.solid_play = true;
+        let mv = pick_move(&board, &config).unwrap().mv;
+        let mut after = board.clone();
+        after.apply_move(&mv);
+        assert!(after.forcing_moves(Color::Black).is_empty(), "{} allows forcing replies", mv.to_uci());
+    }
+
+    #[test]
+    fn hang_check_avoids_leaving_a_piece_en_prise() {
+        // Statically every knight move is worth the same, but the most
+        // central one, Nd5, steps into the e6 pawn's capture.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][1] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[5][4] = Some(Piece::new(PieceType::Pawn, Color::Black));
+
+        let mut config = material_only_config(0);
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "b4d5");
+
+        config.hang_check = true;
+        let mv = pick_move(&board, &config).unwrap().mv;
+        let mut after = board.clone();
+        after.apply_move(&mv);
+        assert!(after.hanging_pieces(Color::White).is_empty(), "{} hangs a piece", mv.to_uci());
+    }
+
+    #[test]
+    fn best_reply_capture_counts_the_exchange_gain_of_one_capture() {
+        // The c5 pawn hits the d4 queen, which e3 defends; a6 and h6 hit the
+        // loose b5 and g5 knights.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][3] = Some(Piece::new(PieceType::Queen, Color::White));
+        board.squares[2][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[4][2] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        let king_step = Move::from_uci("e1f1").unwrap();
+        assert_eq!(best_reply_capture(&board, &king_step), 8.0, "cxd4 exd4 nets a queen for a pawn");
+
+        board.squares[3][3] = None;
+        board.squares[4][1] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[4][6] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[5][0] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[5][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        assert_eq!(best_reply_capture(&board, &king_step), 3.0, "only one knight falls");
+    }
+
+    #[test]
+    fn pawn_advance_gains_space() {
+        // 1.e4 takes d5 and f5 (and opens the queen and bishop); 1.a3
+        // controls nothing beyond the fourth rank.
+        let mut config = AiConfig::new();
+        config.space_module = true;
+        let e4 = Board::from_uci_moves(&["e2e4"]).unwrap();
+        let a3 = Board::from_uci_moves(&["a2a3"]).unwrap();
+        let space = |b: &Board| evaluate_breakdown(b, Color::White, &config).space;
+        assert!(space(&e4) > space(&a3), "e4 {} vs a3 {}", space(&e4), space(&a3));
+        assert_eq!(space(&a3), 0.0);
+        assert_eq!(space(&Board::new()), 0.0);
+        // a6 is covered by the b7 pawn, so the f1 bishop gains only b5.
+        assert_eq!(space_squares(&e4, Color::White), 4);
+    }
+
+    #[test]
+    fn makes_progress_before_fifty_move_draw() {
+        // Rook and pawn up, 92 halfmoves without progress. Rd1 (eyeing the
+        // centre) is the natural move, but only a pawn move resets the clock.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black));
+
+        let mut config = AiConfig::new();
+        config.passed_pawn_module = false;
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        let fresh = pick_move(&board, &config).unwrap().mv;
+        assert!(board.squares[fresh.from.0][fresh.from.1].unwrap().piece_type != PieceType::Pawn);
+
+        board.halfmove_clock = 92;
+        let mv = pick_move(&board, &config).unwrap().mv;
+        let piece = board.squares[mv.from.0][mv.from.1].unwrap().piece_type;
+        assert_eq!(piece, PieceType::Pawn, "stalled with {}", mv.to_uci());
+    }
+
+    #[test]
+    fn king_pressure_tiebreak_prefers_relieving_capture() {
+        // Two hanging black knights: Na5 and Ng3, which eyes f1 and h1 next
+        // to the white king. bxa5 and Rxg3 both win a knight; material only.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[2][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[3][1] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[4][0] = Some(Piece::new(PieceType::Knight, Color::Black));
+        board.squares[2][6] = Some(Piece::new(PieceType::Knight, Color::Black));
+
+        let mut config = material_only_config(1);
+        // The plain tie-break takes with the pawn.
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "b4a5");
+        config.king_pressure_tiebreak = true;
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "a3g3");
+    }
+
+    #[test]
+    fn king_pressure_tiebreak_keeps_the_hang_check_verdict() {
+        // As above, but an h4 pawn guards g3: at depth 0 Rxg3 still scores a
+        // knight, yet leaves the rook en prise.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[2][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[3][1] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[4][0] = Some(Piece::new(PieceType::Knight, Color::Black));
+        board.squares[2][6] = Some(Piece::new(PieceType::Knight, Color::Black));
+        board.squares[3][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+
+        let mut config = material_only_config(0);
+        config.king_pressure_tiebreak = true;
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "a3g3");
+        config.hang_check = true;
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "b4a5");
+    }
+
+    #[test]
+    fn centre_squares_can_be_overridden() {
+        // White knight on f3 attacks e5 and d4 but neither c5 nor f5.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[2][5] = Some(Piece::new(PieceType::Knight, Color::White));
+        let mut w = Weights::default();
+        w.extended_centre_squares.clear();
+        assert!((eval_centre_control(&board, &w) - 2.0 * w.centre_attack).abs() < 1e-9);
+
+        w.centre_squares = vec![(4, 2), (4, 5)];
+        assert_eq!(eval_centre_control(&board, &w), 0.0);
+        w.centre_squares.push((3, 4));
+        w.centre_squares.push((4, 4));
+        assert!((eval_centre_control(&board, &w) - w.centre_attack).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pawn_cache_matches_fresh_evaluation() {
+        reset_pawn_cache();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        let board = Board::from_uci_moves(&["e2e4", "d7d5", "e4d5", "c7c6"]).unwrap();
+        pick_move(&board, &config).unwrap();
+        let stats = pawn_cache_stats();
+        assert!(stats.hits > stats.misses, "pawn cache mostly missed: {stats:?}");
+
+        // Cached and fresh scores agree, including after the weights change.
+        let w = &config.weights;
+        assert_eq!(eval_pawn_structure(&board, w), eval_passed_pawns(&board, w));
+        assert_eq!(eval_pawn_structure(&board, w), eval_passed_pawns(&board, w));
+        let mut tuned = w.clone();
+        tuned.passed_pawn_base += 1.0;
+        assert_eq!(eval_pawn_structure(&board, &tuned), eval_passed_pawns(&board, &tuned));
+    }
+
+    #[test]
+    fn breakdown_cache_reuses_unchanged_position() {
+        let mut cache = BreakdownCache::default();
+        let mut board = Board::new();
+        let config = AiConfig::new();
+        let first = cache.get(&board, &config).total;
+        assert_eq!(cache.get(&board, &config).total, first);
+        assert_eq!(cache.computations(), 1);
+
+        board.apply_move(&Move::from_uci("e2e4").unwrap());
+        let after = cache.get(&board, &config).total;
+        assert_eq!(after, evaluate_breakdown(&board, Color::White, &config).total);
+        assert_eq!(cache.computations(), 2);
+        cache.clear();
+        cache.get(&board, &config);
+        assert_eq!(cache.computations(), 3);
+    }
+
+    #[test]
+    fn doubled_rooks_on_open_file_score_higher() {
+        // Same kings and pawns (a and h files) in both: rooks doubled on the
+        // open d-file vs rooks on the closed a and h files, split by the king.
+        let mut base = Board::empty();
+        base.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        base.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        for col in [0, 7] {
+            base.squares[1][col] = Some(Piece::new(PieceType::Pawn, Color::White));
+            base.squares[6][col] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        }
+        let mut doubled = base.clone();
+        doubled.squares[0][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        doubled.squares[1][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        let mut closed = base.clone();
+        closed.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        closed.squares[0][7] = Some(Piece::new(PieceType::Rook, Color::White));
+
+        let config = AiConfig::new();
+        assert!(evaluate(&doubled, Color::White, &config) > evaluate(&closed, Color::White, &config));
+        assert!((evaluate_breakdown(&doubled, Color::White, &config).rooks - config.weights.doubled_rooks).abs() < 1e-9);
+        assert_eq!(evaluate_breakdown(&closed, Color::White, &config).rooks, 0.0);
+
+        // With the king out of the way the back-rank rooks are connected.
+        closed.set_square(0, 4, None);
+        closed.set_square(1, 4, Some(Piece::new(PieceType::King, Color::White)));
+        let connected = evaluate_breakdown(&closed, Color::White, &config).rooks;
+        assert!((connected - config.weights.connected_rooks).abs() < 1e-9);
+    }
+
+    #[test]
+    fn protected_outpost_knight_scores_higher() {
+        // White Nd5 supported by e4 (outpost) vs the same knight with the
+        // pawn on e3 (loose). No black pawns on the c or e files.
+        let mut outpost = Board::empty();
+        outpost.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        outpost.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        outpost.squares[4][3] = Some(Piece::new(PieceType::Knight, Color::White));
+        outpost.squares[6][0] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        let mut loose = outpost.clone();
+        outpost.squares[3][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+        loose.squares[2][4] = Some(Piece::new(PieceType::Pawn, Color::White));
+
+        let mut config = AiConfig::new();
+        config.centre_module = false;
+        config.passed_pawn_module = false;
+        assert!(evaluate(&outpost, Color::White, &config) > evaluate(&loose, Color::White, &config));
+        assert!(evaluate_breakdown(&outpost, Color::White, &config).outposts > 0.0);
+        assert_eq!(evaluate_breakdown(&loose, Color::White, &config).outposts, 0.0);
+
+        // A black pawn on c7 could kick the knight with ...c6: no outpost.
+        outpost.set_square(6, 2, Some(Piece::new(PieceType::Pawn, Color::Black)));
+        assert_eq!(evaluate_breakdown(&outpost, Color::Black, &config).outposts, 0.0);
+    }
+
+    #[test]
+    fn rook_behind_passed_pawn_scores_higher_than_in_front() {
+        // White passer on d5 with the rook on d1 (behind) or d8 (in front).
+        let mut behind = Board::empty();
+        behind.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        behind.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        behind.squares[4][3] = Some(Piece::new(PieceType::Pawn, Color::White));
+        let mut in_front = behind.clone();
+        behind.squares[0][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        in_front.squares[7][3] = Some(Piece::new(PieceType::Rook, Color::White));
+
+        let config = AiConfig::new();
+        let passers = |board: &Board| evaluate_breakdown(board, Color::White, &config).passed_pawns;
+        assert!(passers(&behind) > passers(&in_front));
+        assert!((passers(&behind) - passers(&in_front) - config.weights.rook_behind_passer).abs() < 1e-9);
+
+        // A black rook blockading the passer from d6 is penalized, less.
+        let mut blockade = in_front.clone();
+        blockade.set_square(7, 3, None);
+        blockade.set_square(5, 3, Some(Piece::new(PieceType::Rook, Color::Black)));
+        let expected = config.weights.rook_behind_passer * ROOK_IN_FRONT_OF_PASSER_FRACTION;
+        let mut no_rook = blockade.clone();
+        no_rook.set_square(5, 3, None);
+        assert!((passers(&blockade) - passers(&no_rook) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn captured_material_sums_piece_values() {
+        // 1.e4 d5 2.exd5 Qxd5 3.Nc3 Qxa2 4.Rxa2
+        let mut board = Board::new();
+        for uci in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a2", "a1a2"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+        assert_eq!(captured_material(&board, Color::White), 2.0);
+        assert_eq!(captured_material(&board, Color::Black), 10.0);
+    }
+
+    #[test]
+    fn deterministic_pick_move_is_stable() {
+        let board = Board::new();
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        config.centre_module = false;
+        config.deterministic = true;
+        // Every opening move ties on score; move priority narrows that to
+        // d2d4/e2e4, and the lower UCI string wins.
+        let first = pick_move(&board, &config).expect("should find a move");
+        assert_eq!(first.mv.to_uci(), "d2d4");
+        for _ in 0..5 {
+            assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), first.mv.to_uci());
+        }
+    }
+
+    #[test]
+    fn king_of_the_hill_engine_walks_to_centre() {
+        let mut board = Board::empty();
+        board.variant = Variant::KingOfTheHill;
+        board.squares[2][4] = Some(Piece::new(PieceType::King, Color::White)); // Ke3
+        board.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black)); // Ka8
+        board.squares[1][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let result = pick_move(&board, &config).expect("should find a move");
+        assert!(CENTRE_SQUARES.contains(&result.mv.to), "king should step onto the hill: {:?}", result.mv.to);
+    }
+
+    #[test]
+    fn evaluate_many_matches_single_evaluations() {
+        // Every position reachable in one move from the start, plus the start.
+        let start = Board::new();
+        let mut positions = vec![start.clone()];
+        for mv in start.generate_legal_moves(Color::White) {
+            let mut clone = start.clone();
+            clone.apply_move(&mv);
+            positions.push(clone);
+        }
+
+        let config = AiConfig::new();
+        let serial = evaluate_many(&positions, &config);
+        let expected: Vec<f64> = positions.iter().map(|b| evaluate(b, b.current_turn, &config)).collect();
+        assert_eq!(serial, expected);
+        assert_eq!(evaluate_many_parallel(&positions, &config, 4), serial);
+        assert_eq!(evaluate_many_parallel(&positions, &config, 64), serial);
+    }
+
+    #[test]
+    fn depth_zero_picks_best_static_evaluation() {
+        // White Rd1 can take an undefended black knight on d5.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][3] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[4][3] = Some(Piece::new(PieceType::Knight, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        let best = board
+            .generate_legal_moves(Color::White)
+            .into_iter()
+            .map(|mv| {
+                let mut clone = board.clone();
+                clone.apply_move(&mv);
+                (evaluate(&clone, Color::White, &config), mv)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+
+        let result = pick_move(&board, &config).expect("should find a move");
+        assert_eq!(result.mv.to_uci(), best.1.to_uci());
+        assert_eq!(result.mv.to_uci(), "d1d5");
+        assert_eq!(result.score, best.0);
+    }
+
+    #[test]
+    fn root_stats_nodes_sum_to_evals() {
+        let board = Board::new();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let result = pick_move(&board, &config).expect("should find a move");
+        assert_eq!(result.root_stats.len(), 20);
+        let total: u64 = result.root_stats.iter().map(|s| s.nodes).sum();
+        assert_eq!(total, result.evals);
+        assert!(result.root_stats.iter().any(|s| s.mv.to_uci() == result.mv.to_uci()));
+    }
+
+    #[test]
+    fn restrictive_checks_are_penalized_more() {
+        // Loose: Re1+ against Ke5, which has six escape squares.
+        let mut loose = Board::empty();
+        loose.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        loose.squares[0][4] = Some(Piece::new(PieceType::Rook, Color::White));
+        loose.squares[4][4] = Some(Piece::new(PieceType::King, Color::Black));
+        loose.current_turn = Color::Black;
+        // Near-mate: Ra8+ against Kh8 with Ph7; the only reply is Kg7.
+        let mut tight = Board::empty();
+        tight.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        tight.squares[7][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        tight.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        tight.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        tight.current_turn = Color::Black;
+        assert_eq!(tight.legal_move_count(Color::Black), 1);
+
+        let config = AiConfig::new();
+        let loose_check = evaluate_breakdown(&loose, Color::White, &config).mate;
+        let tight_check = evaluate_breakdown(&tight, Color::White, &config).mate;
+        assert!(loose_check > config.weights.check_penalty);
+        assert!(tight_check > loose_check, "tight {tight_check} vs loose {loose_check}");
+    }
+
+    #[test]
+    fn checkmate_detected_without_mate_module() {
+        // Back-rank mate: White Kg1 behind f2 g2 h2, Black Ra1.
+        let mut board = Board::empty();
+        board.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[1][5] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][6] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::Black));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.current_turn = Color::White;
+
+        let mut config = AiConfig::new();
+        config.mate_module = false;
+        config.centre_module = false;
+        config.passed_pawn_module = false;
+        config.draw_penalty_module = false;
+        let score = evaluate(&board, Color::White, &config);
+        assert!(score < -9000.0, "mated side should score as lost, got {score}");
+        assert!(evaluate(&board, Color::Black, &config) > 9000.0);
+        assert_eq!(evaluate_breakdown(&board, Color::White, &config).total, score);
+    }
+
+    #[test]
+    fn move_ordering_is_total_and_deterministic() {
+        let board = Board::new();
+        let mut forward = board.generate_legal_moves(Color::White);
+        let mut reversed: Vec<Move> = forward.iter().rev().cloned().collect();
+        order_moves(&board, &mut forward);
+        order_moves(&board, &mut reversed);
+        let forward: Vec<String> = forward.iter().map(|m| m.to_uci()).collect();
+        let reversed: Vec<String> = reversed.iter().map(|m| m.to_uci()).collect();
+        assert_eq!(forward, reversed);
+        assert_eq!(forward[..2], ["d2d4", "e2e4"]);
+
+        // The allocation-free key agrees with UCI string order.
+        let promotions = ["a7a8b", "a7a8n", "a7a8q", "a7a8r", "b1c3", "b7b8"];
+        for pair in promotions.windows(2) {
+            let (a, b) = (Move::from_uci(pair[0]).unwrap(), Move::from_uci(pair[1]).unwrap());
+            assert!(uci_order_key(&a) < uci_order_key(&b), "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn centralising_quiet_moves_are_ordered_first() {
+        let board = Board::new();
+        let to_centre = Move::from_uci("g1f3").unwrap();
+        let to_rim = Move::from_uci("g1h3").unwrap();
+        assert!(move_priority(&board, &to_centre) > move_priority(&board, &to_rim));
+
+        // Captures still dominate any quiet bonus.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[7][4] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[3][1] = Some(Piece::new(PieceType::Knight, Color::White));
+        board.squares[5][0] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        let capture = Move::from_uci("b4a6").unwrap();
+        let quiet = Move::from_uci("b4d5").unwrap();
+        assert!(move_priority(&board, &capture) > move_priority(&board, &quiet));
+    }
+
+    #[test]
+    fn play_weighted_searches_each_side_with_its_own_weights() {
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        config.deterministic = true;
+        let materialist = Weights { centre_attack: 0.0, centre_occupy: 0.0, extended_centre_attack: 0.0, ..Weights::default() };
+        let positional = Weights { centre_attack: 1.0, centre_occupy: 3.0, ..Weights::default() };
+        let game = play_weighted(&materialist, &positional, &config, 12);
+        assert_eq!(game.moves.len(), 12);
+
+        let white = AiConfig { weights: materialist, ..config.clone() };
+        let black = AiConfig { weights: positional, ..config.clone() };
+        let mut board = Board::new();
+        let mut swapped_differs = false;
+        for mv in &game.moves {
+            let (own, other) = if board.current_turn == Color::White { (&white, &black) } else { (&black, &white) };
+            assert_eq!(pick_move(&board, own).unwrap().mv.to_uci(), mv.to_uci());
+            swapped_differs |= pick_move(&board, other).unwrap().mv.to_uci() != mv.to_uci();
+            board.apply_move(mv);
+        }
+        // The weights matter: at some ply the other side's would have chosen differently.
+        assert!(swapped_differs);
+        assert_eq!(board.position_hash(), game.board.position_hash());
+    }
+
+    #[test]
+    fn book_variety_stays_in_book() {
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        config.opening_book = true;
+        let board = Board::from_uci_moves(&["e2e4"]).unwrap();
+        let book: Vec<String> = book_moves(&board).iter().map(|(m, _)| m.to_uci()).collect();
+        assert!(book.len() > 1);
+        // Without variety, always the most popular reply.
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), book[0]);
+
+        config.book_variety = true;
+        let mut seen = std::collections::HashSet::new();
+        for seed in 0..32 {
+            config.seed = Some(seed);
+            let mv = pick_move(&board, &config).unwrap().mv.to_uci();
+            assert!(book.contains(&mv), "seed {seed} left the book with {mv}");
+            assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), mv, "seed {seed} is not reproducible");
+            seen.insert(mv);
+        }
+        assert!(seen.len() > 1);
+
+        // Out of book the search decides as usual.
+        let out = Board::from_uci_moves(&["a2a3"]).unwrap();
+        config.opening_book = false;
+        let strict = pick_move(&out, &config).unwrap().mv.to_uci();
+        config.opening_book = true;
+        assert_eq!(pick_move(&out, &config).unwrap().mv.to_uci(), strict);
+    }
+
+    #[test]
+    fn custom_eval_replaces_the_modules() {
+        // The built-in eval keeps the queen central; pure material grabs a7.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[3][3] = Some(Piece::new(PieceType::Queen, Color::White));
+        board.squares[1][7] = Some(Piece::new(PieceType::Pawn, Color::White));
+        board.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        board.squares[6][6] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][7] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        board.squares[6][0] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        config.deterministic = true;
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "d4d5");
+
+        config.custom_eval = Some(Arc::new(|board: &Board, color: Color| {
+            let material = eval_material(board) * 2.0;
+            if color == Color::White { material } else { -material }
+        }));
+        let custom = pick_move(&board, &config).unwrap();
+        assert_eq!(custom.mv.to_uci(), "d4a7");
+        assert_eq!(custom.score, 16.0);
+    }
+
+    #[test]
+    fn no_early_queen_until_forbids_queen_sorties() {
+        // 3.Qh5+ exploits the weakened e8-h5 diagonal; a coached engine
+        // develops a piece instead.
+        let board = Board::from_uci_moves(&["e2e4", "f7f6", "d2d4", "a7a6"]).unwrap();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.deterministic = true;
+        config.development_module = true;
+        assert_eq!(pick_move(&board, &config).unwrap().mv.to_uci(), "d1h5");
+        config.no_early_queen_until = 5;
+        let taught = pick_move(&board, &config).unwrap().mv;
+        let piece = board.squares[taught.from.0][taught.from.1].unwrap().piece_type;
+        assert!(matches!(piece, PieceType::Knight | PieceType::Bishop), "played {}", taught.to_uci());
+
+        // With only queen moves legal, the queen moves anyway.
+        let mut stuck = Board::empty();
+        stuck.squares[0][0] = Some(Piece::new(PieceType::King, Color::White));
+        stuck.squares[1][0] = Some(Piece::new(PieceType::Pawn, Color::White));
+        stuck.squares[0][3] = Some(Piece::new(PieceType::Queen, Color::White));
+        stuck.squares[2][0] = Some(Piece::new(PieceType::Pawn, Color::Black));
+        stuck.squares[7][1] = Some(Piece::new(PieceType::Rook, Color::Black));
+        stuck.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        let mv = pick_move(&stuck, &config).unwrap().mv;
+        assert_eq!(mv.from, (0, 3));
+    }
+
+    #[test]
+    fn analyze_game_flags_blunder() {
+        // 1.e4 e5 2.Qh5 Nc6 3.Bc4 Nf6?? allows 4.Qxf7#.
+        let moves: Vec<Move> = ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6"]
+            .iter()
+            .map(|uci| Move::from_uci(uci).unwrap())
+            .collect();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let points = analyze_game(Variant::Standard, &moves, &config);
+        assert_eq!(points.len(), moves.len());
+        let nf6 = &points[5];
+        assert!(nf6.blunder, "Nf6 should be flagged (played {}, best {})", nf6.played_score, nf6.best_score);
+        assert!(nf6.played_score < -9000.0);
+    }
+
+    #[test]
+    fn analyze_game_judges_moves_the_root_rules_would_not_play() {
+        // 1.a3 is out of book, 4.Qh5 breaks the early-queen rule, and
+        // 5.Nc3?? misses Qxf7#, which mate-stop would leave as the only
+        // move searched.
+        let moves: Vec<Move> = ["a2a3", "e7e5", "e2e4", "b8c6", "f1c4", "a7a6", "d1h5", "g8f6", "b1c3", "a6a5", "g1f3", "a5a4"]
+            .iter()
+            .map(|uci| Move::from_uci(uci).unwrap())
+            .collect();
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        config.stop_on_mate = true;
+        config.opening_book = true;
+        config.no_early_queen_until = 10;
+        let points = analyze_game(Variant::Standard, &moves, &config);
+        assert_eq!(points.len(), moves.len());
+        let nc3 = &points[8];
+        assert_eq!(nc3.best.to_uci(), "h5f7");
+        assert!(nc3.blunder && nc3.best_score >= MATE_THRESHOLD);
+        assert_eq!(points[6].played.to_uci(), "d1h5");
+
+        // King of the Hill: the king walk to e4 is legal and scored there.
+        let walk: Vec<Move> = ["e2e3", "a7a6", "e1e2", "a6a5", "e2d3", "a5a4", "d3e4"].iter().map(|uci| Move::from_uci(uci).unwrap()).collect();
+        let points = analyze_game(Variant::KingOfTheHill, &walk, &config);
+        assert_eq!(points.len(), walk.len());
+        assert!(points[6].played_score >= MATE_THRESHOLD);
+        assert!(analyze_game(Variant::Standard, &walk, &config)[6].played_score < MATE_THRESHOLD);
+    }
+
+    #[test]
+    fn avoids_repetition_when_winning() {
+        // White Ke1 + Ra1 vs Black Kh8. Shuffle Ra1-a2, Kh8-g8, Ra2-a1, Kg8-h8
+        // so that Ra1-a2 now would repeat a position.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.current_turn = Color::White;
+        board.position_history.push(board.position_hash());
+        for uci in ["a1a2", "h8g8", "a2a1", "g8h8"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+        assert!(!board.game_over);
+
+        // Material only, so every quiet move scores the same.
+        let mut config = AiConfig::new();
+        config.depth = 0;
+        config.mate_module = false;
+        config.centre_module = false;
+        config.passed_pawn_module = false;
+        config.draw_penalty_module = false;
+        for seed in 0..16 {
+            config.seed = Some(seed);
+            let result = pick_move(&board, &config).unwrap();
+            assert_ne!(result.mv.to_uci(), "a1a2", "seed {seed} repeated the position");
+        }
+    }
+
+    #[test]
+    fn draw_contempt_is_the_search_draw_score() {
+        // As above, Ra1-a2 repeats a position; after it, any reply is
+        // searched from a repetition, which scores the draw.
+        let mut board = Board::empty();
+        board.squares[0][4] = Some(Piece::new(PieceType::King, Color::White));
+        board.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+        board.position_history.push(board.position_hash());
+        for uci in ["a1a2", "h8g8", "a2a1", "g8h8"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        let repeat_score = |config: &AiConfig| {
+            let result = pick_move(&board, config).unwrap();
+            result.root_stats.iter().find(|s| s.mv.to_uci() == "a1a2").unwrap().score
+        };
+        for contempt in [5.0, 1.5] {
+            config.weights.draw_contempt = contempt;
+            assert!((repeat_score(&config) + contempt).abs() < 1e-9);
+        }
+        config.draw_penalty_module = false;
+        assert_eq!(repeat_score(&config), 0.0);
+    }
+
+    #[test]
+    fn avoids_stalemating_when_winning() {
+        // White: Kb6, Qc2. Black: Ka8. Qc7 stalemates; Qc8# (among others) mates.
+        let mut board = Board::empty();
+        board.squares[5][1] = Some(Piece::new(PieceType::King, Color::White));  // Kb6
+        board.squares[1][2] = Some(Piece::new(PieceType::Queen, Color::White)); // Qc2
+        board.squares[7][0] = Some(Piece::new(PieceType::King, Color::Black));  // Ka8
+        board.current_turn = Color::White;
+
+        let mut stalemating = board.clone();
+        stalemating.apply_move(&Move { from: (1, 2), to: (6, 2), promotion: None });
+        assert!(stalemating.is_stalemate(), "Qc7 should stalemate in the test position");
+
+        let mut config = AiConfig::new();
+        config.depth = 1;
+        config.auto_deepen = false;
+        for _ in 0..5 {
+            let result = pick_move(&board, &config).expect("should find a move");
+            let mut after = board.clone();
+            after.apply_move(&result.mv);
+            assert!(!after.is_stalemate(), "engine stalemated with {:?} -> {:?}", result.mv.from, result.mv.to);
+        }
+    }
+
 }
@@ -0,0 +1,74 @@
+use std::fmt;
+
+use crate::piece::Color;
+
+/// Why a position or move could not be loaded or played. `Display` gives a
+/// message fit to show a player.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ChessError {
+    /// A FEN string that `Board::from_fen` could not read.
+    InvalidFen { reason: String },
+    /// Text that is not a move at all, e.g. "e2i9"; `what` is the input.
+    ParseError { what: String },
+    /// A well-formed move that is not legal in the position.
+    IllegalMove { mv: String },
+    /// SAN that fits more than one legal move, e.g. "Rd1" with two rooks.
+    AmbiguousSan { san: String },
+    /// A pawn move to the last rank without a promotion piece.
+    PromotionRequired,
+    /// A promotion piece given for a move that is not a promotion.
+    NotAPromotion,
+    /// A promotion to a king or pawn, or to no known piece.
+    InvalidPromotion { reason: String },
+    /// A move was attempted after the game ended.
+    GameOver,
+    /// `Board::verify_and_apply` was given the hash of another position.
+    PositionMismatch { expected: u64, actual: u64 },
+    /// PGN that `GameRecord::from_pgn` could not read, e.g. an unterminated
+    /// comment.
+    InvalidPgn { reason: String },
+    /// A PGN move that cannot be played in the game so far; `reason` is the
+    /// underlying error's message.
+    PgnMove { move_number: u32, color: Color, san: String, reason: String },
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessError::InvalidFen { reason } => write!(f, "Invalid FEN: {reason}"),
+            ChessError::ParseError { what } => write!(f, "Malformed move '{what}'"),
+            ChessError::IllegalMove { mv } => write!(f, "Illegal move '{mv}'"),
+            ChessError::AmbiguousSan { san } => write!(f, "Ambiguous move '{san}'"),
+            ChessError::PromotionRequired => write!(f, "Promotion required: choose a queen, rook, bishop or knight"),
+            ChessError::NotAPromotion => write!(f, "Move is not a promotion"),
+            ChessError::InvalidPromotion { reason } => write!(f, "{reason}"),
+            ChessError::GameOver => write!(f, "Game is already over"),
+            ChessError::PositionMismatch { expected, actual } => {
+                write!(f, "Position mismatch: expected {expected:016x}, have {actual:016x}")
+            }
+            ChessError::InvalidPgn { reason } => write!(f, "Invalid PGN: {reason}"),
+            ChessError::PgnMove { move_number, color, san, reason } => {
+                let dots = if *color == Color::White { "." } else { "..." };
+                write!(f, "Move {move_number}{dots} {san}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_the_input() {
+        assert_eq!(ChessError::IllegalMove { mv: "e2e5".to_string() }.to_string(), "Illegal move 'e2e5'");
+        assert_eq!(ChessError::InvalidFen { reason: "expected 8 ranks, found 7".to_string() }.to_string(), "Invalid FEN: expected 8 ranks, found 7");
+        assert_eq!(ChessError::GameOver.to_string(), "Game is already over");
+        let err: Box<dyn std::error::Error> = Box::new(ChessError::NotAPromotion);
+        assert_eq!(err.to_string(), "Move is not a promotion");
+        let pgn = ChessError::PgnMove { move_number: 8, color: Color::Black, san: "Nxe5".to_string(), reason: "Illegal move 'Nxe5'".to_string() };
+        assert_eq!(pgn.to_string(), "Move 8... Nxe5: Illegal move 'Nxe5'");
+    }
+}
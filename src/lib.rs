@@ -1,7 +1,11 @@
 pub mod board;
 pub mod engine;
+pub mod error;
 pub mod moves;
+pub mod opening;
+pub mod pgn;
 pub mod piece;
+pub mod testpositions;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm_api;
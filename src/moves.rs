@@ -1,7 +1,25 @@
 use serde::{Deserialize, Serialize};
 
+use crate::board::Board;
 use crate::piece::PieceType;
 
+/// Parse a square name such as "e4" into `(row, col)`, e.g. (3, 4).
+pub fn square_to_index(name: &str) -> Option<(usize, usize)> {
+    match name.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some(((rank - b'1') as usize, (file - b'a') as usize)),
+        _ => None,
+    }
+}
+
+/// Name of the square at `(row, col)`, e.g. (3, 4) → "e4". Panics if the
+/// square is off the board.
+pub fn index_to_square((row, col): (usize, usize)) -> String {
+    assert!(row < 8 && col < 8, "square ({row}, {col}) is off the board");
+    let file = (b'a' + col as u8) as char;
+    let rank = (b'1' + row as u8) as char;
+    format!("{file}{rank}")
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Move {
     pub from: (usize, usize),
@@ -12,10 +30,6 @@ pub struct Move {
 impl Move {
     /// Convert to UCI notation, e.g. "e2e4", "a7a8q"
     pub fn to_uci(&self) -> String {
-        let fc = (b'a' + self.from.1 as u8) as char;
-        let fr = (b'1' + self.from.0 as u8) as char;
-        let tc = (b'a' + self.to.1 as u8) as char;
-        let tr = (b'1' + self.to.0 as u8) as char;
         let promo = match self.promotion {
             Some(PieceType::Queen) => "q",
             Some(PieceType::Rook) => "r",
@@ -23,34 +37,141 @@ impl Move {
             Some(PieceType::Knight) => "n",
             _ => "",
         };
-        format!("{fc}{fr}{tc}{tr}{promo}")
+        format!("{}{}{promo}", index_to_square(self.from), index_to_square(self.to))
+    }
+
+    /// Standard algebraic notation for this move in `board`, the position
+    /// before it is played: "Nf3", "exd5", "O-O", "e8=Q+". Falls back to
+    /// UCI if the move is not legal there. See `Board::move_to_san`.
+    pub fn to_san(&self, board: &Board) -> String {
+        board.move_to_san(self).unwrap_or_else(|| self.to_uci())
+    }
+
+    /// The legal move in `board` written `san`, e.g. "Nbd2", "exd5", "O-O"
+    /// or "e8=Q+". `None` if it is malformed, illegal or ambiguous; use
+    /// `Board::parse_san` to find out which.
+    pub fn from_san(san: &str, board: &Board) -> Option<Move> {
+        board.parse_san(san).ok()
     }
 
     /// Parse from UCI notation
     pub fn from_uci(s: &str) -> Option<Move> {
-        let bytes = s.as_bytes();
-        if bytes.len() < 4 {
-            return None;
-        }
-        let fc = (bytes[0] - b'a') as usize;
-        let fr = (bytes[1] - b'1') as usize;
-        let tc = (bytes[2] - b'a') as usize;
-        let tr = (bytes[3] - b'1') as usize;
-        let promotion = if bytes.len() > 4 {
-            match bytes[4] {
-                b'q' => Some(PieceType::Queen),
-                b'r' => Some(PieceType::Rook),
-                b'b' => Some(PieceType::Bishop),
-                b'n' => Some(PieceType::Knight),
-                _ => None,
-            }
-        } else {
-            None
+        let from = square_to_index(s.get(0..2)?)?;
+        let to = square_to_index(s.get(2..4)?)?;
+        let promotion = match s.as_bytes().get(4) {
+            Some(b'q') => Some(PieceType::Queen),
+            Some(b'r') => Some(PieceType::Rook),
+            Some(b'b') => Some(PieceType::Bishop),
+            Some(b'n') => Some(PieceType::Knight),
+            _ => None,
         };
         Some(Move {
-            from: (fr, fc),
-            to: (tr, tc),
+            from,
+            to,
             promotion,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_names_round_trip() {
+        assert_eq!(square_to_index("a1"), Some((0, 0)));
+        assert_eq!(square_to_index("h8"), Some((7, 7)));
+        assert_eq!(square_to_index("e4"), Some((3, 4)));
+        assert_eq!(index_to_square((0, 0)), "a1");
+        assert_eq!(index_to_square((7, 7)), "h8");
+        for row in 0..8 {
+            for col in 0..8 {
+                assert_eq!(square_to_index(&index_to_square((row, col))), Some((row, col)));
+            }
+        }
+    }
+
+    #[test]
+    fn san_disambiguates_file_then_rank_then_both() {
+        use crate::piece::{Color, Piece};
+        let san = |pieces: &[(PieceType, &str)], uci: &str| {
+            let mut board = Board::empty();
+            board.squares[0][7] = Some(Piece::new(PieceType::King, Color::White));
+            board.squares[7][7] = Some(Piece::new(PieceType::King, Color::Black));
+            for &(pt, sq) in pieces {
+                let (r, c) = square_to_index(sq).unwrap();
+                board.squares[r][c] = Some(Piece::new(pt, Color::White));
+            }
+            Move::from_uci(uci).unwrap().to_san(&board)
+        };
+        use PieceType::{Knight, Queen};
+        assert_eq!(san(&[(Knight, "b1"), (Knight, "f1")], "b1d2"), "Nbd2");
+        assert_eq!(san(&[(Knight, "g1"), (Knight, "g5")], "g1f3"), "N1f3");
+        assert_eq!(san(&[(Knight, "g1"), (Knight, "g5")], "g5f3"), "N5f3");
+        assert_eq!(san(&[(Knight, "g1"), (Knight, "b1")], "g1f3"), "Nf3");
+        assert_eq!(san(&[(Queen, "a1"), (Queen, "a3"), (Queen, "c1")], "a1b2"), "Qa1b2+");
+    }
+
+    #[test]
+    fn san_for_pawn_captures() {
+        let board = Board::from_uci_moves(&["e2e4", "d7d5"]).unwrap();
+        assert_eq!(Move::from_uci("e4d5").unwrap().to_san(&board), "exd5");
+        assert_eq!(Move::from_uci("e4e5").unwrap().to_san(&board), "e5");
+        // En passant is written as a capture on the target square.
+        let board = Board::from_uci_moves(&["e2e4", "a7a6", "e4e5", "d7d5"]).unwrap();
+        assert_eq!(Move::from_uci("e5d6").unwrap().to_san(&board), "exd6");
+        let board = Board::from_uci_moves(&["d2d4", "e7e5"]).unwrap();
+        assert_eq!(Move::from_uci("d4e5").unwrap().to_san(&board), "dxe5");
+        // Not legal here: shown as UCI.
+        assert_eq!(Move::from_uci("d4d6").unwrap().to_san(&board), "d4d6");
+    }
+
+    #[test]
+    fn from_san_replays_the_opera_game() {
+        // Morphy v Duke of Brunswick and Count Isouard, Paris 1858.
+        let game = [
+            "e4", "e5", "Nf3", "d6", "d4", "Bg4", "dxe5", "Bxf3", "Qxf3", "dxe5", "Bc4", "Nf6", "Qb3", "Qe7",
+            "Nc3", "c6", "Bg5", "b5", "Nxb5", "cxb5", "Bxb5+", "Nbd7", "O-O-O", "Rd8", "Rxd7", "Rxd7", "Rd1",
+            "Qe6", "Bxd7+", "Nxd7", "Qb8+", "Nxb8", "Rd8#",
+        ];
+        let mut board = Board::new();
+        for san in game {
+            let m = Move::from_san(san, &board).unwrap_or_else(|| panic!("{san} did not resolve"));
+            assert_eq!(m.to_san(&board), san);
+            board.apply_move(&m);
+        }
+        assert_eq!(board.winner(), Some(crate::piece::Color::White));
+    }
+
+    #[test]
+    fn from_san_rejects_illegal_and_ambiguous_moves() {
+        let board = Board::from_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6", "b1c3", "g8f6", "d2d3", "d7d6"]).unwrap();
+        assert_eq!(Move::from_san("Nd2", &board).unwrap().to_uci(), "f3d2");
+        assert!(Move::from_san("Ne2", &board).is_some_and(|m| m.from == (2, 2)));
+        assert!(Move::from_san("Nb5+", &board).is_some(), "check markers are ignored");
+        assert!(Move::from_san("O-O", &board).is_none(), "the f1 bishop is in the way");
+        assert!(Move::from_san("Qe2", &board).is_some());
+        assert!(Move::from_san("Qh5", &board).is_none(), "the f3 knight is in the way");
+        assert!(Move::from_san("Zz9", &board).is_none());
+
+        // Both rooks reach d1: only the disambiguated forms resolve.
+        use crate::piece::{Color, Piece};
+        let mut rooks = Board::empty();
+        rooks.squares[0][6] = Some(Piece::new(PieceType::King, Color::White));
+        rooks.squares[7][6] = Some(Piece::new(PieceType::King, Color::Black));
+        rooks.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::White));
+        rooks.squares[0][5] = Some(Piece::new(PieceType::Rook, Color::White));
+        assert!(Move::from_san("Rd1", &rooks).is_none());
+        assert_eq!(Move::from_san("Rad1", &rooks).unwrap().to_uci(), "a1d1");
+        assert_eq!(Move::from_san("Rfd1", &rooks).unwrap().to_uci(), "f1d1");
+    }
+
+    #[test]
+    fn invalid_square_names_are_rejected() {
+        for name in ["i9", "a0", "h9", "", "e", "e44", "E4"] {
+            assert_eq!(square_to_index(name), None, "{name:?}");
+        }
+        assert!(Move::from_uci("e2i9").is_none());
+        assert!(Move::from_uci("A2a4").is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::board::{Board, Variant};
+use crate::moves::Move;
+
+/// Named openings as UCI move sequences. Some openings appear more than
+/// once to catch common transpositions.
+const OPENINGS: &[(&str, &[&str])] = &[
+    ("King's Pawn Opening", &["e2e4"]),
+    ("Queen's Pawn Opening", &["d2d4"]),
+    ("English Opening", &["c2c4"]),
+    ("Réti Opening", &["g1f3"]),
+    ("Bird's Opening", &["f2f4"]),
+    ("Open Game", &["e2e4", "e7e5"]),
+    ("Sicilian Defence", &["e2e4", "c7c5"]),
+    ("French Defence", &["e2e4", "e7e6"]),
+    ("Caro-Kann Defence", &["e2e4", "c7c6"]),
+    ("Scandinavian Defence", &["e2e4", "d7d5"]),
+    ("Alekhine's Defence", &["e2e4", "g8f6"]),
+    ("Pirc Defence", &["e2e4", "d7d6", "d2d4", "g8f6"]),
+    ("Modern Defence", &["e2e4", "g7g6"]),
+    ("King's Gambit", &["e2e4", "e7e5", "f2f4"]),
+    ("Vienna Game", &["e2e4", "e7e5", "b1c3"]),
+    ("King's Knight Opening", &["e2e4", "e7e5", "g1f3"]),
+    ("Petrov's Defence", &["e2e4", "e7e5", "g1f3", "g8f6"]),
+    ("Philidor Defence", &["e2e4", "e7e5", "g1f3", "d7d6"]),
+    ("Scotch Game", &["e2e4", "e7e5", "g1f3", "b8c6", "d2d4"]),
+    ("Italian Game", &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4"]),
+    ("Ruy Lopez", &["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]),
+    ("Four Knights Game", &["e2e4", "e7e5", "g1f3", "b8c6", "b1c3", "g8f6"]),
+    ("Two Knights Defence", &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6"]),
+    ("Giuoco Piano", &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5"]),
+    ("Evans Gambit", &["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "b2b4"]),
+    ("Sicilian Defence: Najdorf Variation", &["e2e4", "c7c5", "g1f3", "d7d6", "d2d4", "c5d4", "f3d4", "g8f6", "b1c3", "a7a6"]),
+    ("Sicilian Defence: Dragon Variation", &["e2e4", "c7c5", "g1f3", "d7d6", "d2d4", "c5d4", "f3d4", "g8f6", "b1c3", "g7g6"]),
+    ("Sicilian Defence: Alapin Variation", &["e2e4", "c7c5", "c2c3"]),
+    ("French Defence: Advance Variation", &["e2e4", "e7e6", "d2d4", "d7d5", "e4e5"]),
+    ("Caro-Kann Defence: Advance Variation", &["e2e4", "c7c6", "d2d4", "d7d5", "e4e5"]),
+    ("Queen's Gambit", &["d2d4", "d7d5", "c2c4"]),
+    ("Queen's Gambit Accepted", &["d2d4", "d7d5", "c2c4", "d5c4"]),
+    ("Queen's Gambit Declined", &["d2d4", "d7d5", "c2c4", "e7e6"]),
+    ("Slav Defence", &["d2d4", "d7d5", "c2c4", "c7c6"]),
+    ("London System", &["d2d4", "d7d5", "c1f4"]),
+    ("London System", &["d2d4", "g8f6", "c1f4"]),
+    ("Indian Defence", &["d2d4", "g8f6"]),
+    ("King's Indian Defence", &["d2d4", "g8f6", "c2c4", "g7g6"]),
+    ("Grünfeld Defence", &["d2d4", "g8f6", "c2c4", "g7g6", "b1c3", "d7d5"]),
+    ("Nimzo-Indian Defence", &["d2d4", "g8f6", "c2c4", "e7e6", "b1c3", "f8b4"]),
+    ("Queen's Indian Defence", &["d2d4", "g8f6", "c2c4", "e7e6", "g1f3", "b7b6"]),
+    ("Benoni Defence", &["d2d4", "g8f6", "c2c4", "c7c5"]),
+    ("Dutch Defence", &["d2d4", "f7f5"]),
+    ("English Opening: Reversed Sicilian", &["c2c4", "e7e5"]),
+    ("English Opening: Symmetrical Variation", &["c2c4", "c7c5"]),
+];
+
+/// Name of the most specific opening whose moves are a prefix of `moves`,
+/// or `None` if the game left the table's lines straight away.
+pub fn opening_name(moves: &[Move]) -> Option<&'static str> {
+    let played: Vec<String> = moves.iter().map(Move::to_uci).collect();
+    OPENINGS
+        .iter()
+        .filter(|(_, line)| line.len() <= played.len() && line.iter().zip(&played).all(|(a, b)| *a == b))
+        .max_by_key(|(_, line)| line.len())
+        .map(|(name, _)| *name)
+}
+
+/// Book positions by `position_hash`, each with its continuations in UCI
+/// and how many table lines take them.
+fn book() -> &'static HashMap<u64, Vec<(&'static str, u32)>> {
+    static BOOK: OnceLock<HashMap<u64, Vec<(&'static str, u32)>>> = OnceLock::new();
+    BOOK.get_or_init(|| {
+        let mut book: HashMap<u64, Vec<(&'static str, u32)>> = HashMap::new();
+        for (_, line) in OPENINGS {
+            let mut board = Board::new();
+            for &uci in line.iter() {
+                let entry = book.entry(board.position_hash()).or_default();
+                match entry.iter_mut().find(|(m, _)| *m == uci) {
+                    Some((_, count)) => *count += 1,
+                    None => entry.push((uci, 1)),
+                }
+                if board.apply_uci(uci).is_err() {
+                    break;
+                }
+            }
+        }
+        book
+    })
+}
+
+/// Opening book moves for `board` with their weights (the number of named
+/// lines in the table that continue with the move), most popular first.
+/// Empty out of book and in variants other than standard chess.
+pub fn book_moves(board: &Board) -> Vec<(Move, u32)> {
+    if board.variant != Variant::Standard {
+        return Vec::new();
+    }
+    let mut moves: Vec<(Move, u32)> = book()
+        .get(&board.position_hash())
+        .into_iter()
+        .flatten()
+        .filter_map(|&(uci, count)| Move::from_uci(uci).map(|m| (m, count)))
+        .filter(|(m, _)| board.is_legal(m))
+        .collect();
+    moves.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_uci().cmp(&b.0.to_uci())));
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(uci: &[&str]) -> Vec<Move> {
+        uci.iter().map(|m| Move::from_uci(m).unwrap()).collect()
+    }
+
+    #[test]
+    fn identifies_most_specific_opening() {
+        let ruy = moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]);
+        assert_eq!(opening_name(&ruy), Some("Ruy Lopez"));
+        // Later moves outside the table keep the deepest match.
+        let ruy_deeper = moves(&["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5a4"]);
+        assert_eq!(opening_name(&ruy_deeper), Some("Ruy Lopez"));
+        assert_eq!(opening_name(&moves(&["e2e4", "e7e5", "g1f3"])), Some("King's Knight Opening"));
+        assert_eq!(opening_name(&moves(&["a2a3"])), None);
+        assert_eq!(opening_name(&[]), None);
+    }
+
+    #[test]
+    fn book_weights_count_lines() {
+        let start = book_moves(&Board::new());
+        assert_eq!(start[0].0.to_uci(), "e2e4");
+        assert!(start.iter().any(|(m, _)| m.to_uci() == "f2f4"));
+        let after_e4 = book_moves(&Board::from_uci_moves(&["e2e4"]).unwrap());
+        let e5 = after_e4.iter().find(|(m, _)| m.to_uci() == "e7e5").unwrap().1;
+        let c6 = after_e4.iter().find(|(m, _)| m.to_uci() == "c7c6").unwrap().1;
+        assert!(e5 > c6);
+        assert!(book_moves(&Board::from_uci_moves(&["a2a3"]).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn table_lines_are_valid_uci() {
+        for (name, line) in OPENINGS {
+            assert!(crate::board::Board::from_uci_moves(line).is_some(), "{name} has an illegal line");
+        }
+    }
+}
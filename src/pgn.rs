@@ -0,0 +1,457 @@
+use crate::board::{Board, Variant};
+use crate::error::ChessError;
+use crate::moves::Move;
+use crate::piece::Color;
+
+/// The tags every PGN game carries, in export order, with the values used
+/// until `GameRecord::set_tag` overrides them. `Result` is always taken
+/// from the game itself.
+const SEVEN_TAG_ROSTER: [(&str, &str); 6] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+];
+
+/// PGN export wraps movetext lines at this many characters.
+const PGN_LINE_WIDTH: usize = 80;
+
+/// Game termination markers that end PGN movetext.
+const RESULT_TOKENS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// A piece of PGN text, from `tokenize`.
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    /// A move, move number or result.
+    Symbol(String),
+}
+
+fn invalid(reason: &str) -> ChessError {
+    ChessError::InvalidPgn { reason: reason.to_string() }
+}
+
+/// Split PGN into tag pairs and movetext symbols, dropping comments (`{}`
+/// and `;` to end of line), NAGs such as `$1`, `%` escape lines and
+/// variations in `()`.
+fn tokenize(pgn: &str) -> Result<Vec<Token>, ChessError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        let at_line_start = line_start;
+        line_start = c == '\n';
+        match c {
+            c if c.is_whitespace() => {}
+            '%' | ';' if c == ';' || at_line_start => {
+                chars.by_ref().find(|&c| c == '\n');
+                line_start = true;
+            }
+            '{' => {
+                chars.by_ref().find(|&c| c == '}').ok_or_else(|| invalid("unterminated comment"))?;
+            }
+            '(' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next().ok_or_else(|| invalid("unterminated variation"))? {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        '{' => {
+                            chars.by_ref().find(|&c| c == '}').ok_or_else(|| invalid("unterminated comment"))?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            ')' => return Err(invalid("')' outside a variation")),
+            '$' => {
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+            }
+            '[' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"' && *c != ']') {
+                    name.push(c);
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if name.is_empty() || chars.next() != Some('"') {
+                    return Err(invalid("malformed tag pair"));
+                }
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or_else(|| invalid("unterminated tag value"))? {
+                        '"' => break,
+                        '\\' => value.extend(chars.next()),
+                        c => value.push(c),
+                    }
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.next() != Some(']') {
+                    return Err(invalid(&format!("tag {name} is missing its ']'")));
+                }
+                tokens.push(Token::Tag(name, value));
+            }
+            _ => {
+                let mut symbol = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}()[];$".contains(*c)) {
+                    symbol.push(c);
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// A game as played: its starting position, every move since and the PGN
+/// tags describing it. Moves are checked as they are recorded, so the
+/// record always replays.
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    start: Board,
+    moves: Vec<Move>,
+    /// The position after `moves`.
+    board: Board,
+    tags: Vec<(String, String)>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self::new(Board::new())
+    }
+}
+
+impl GameRecord {
+    /// An empty record of a game starting from `start`.
+    pub fn new(start: Board) -> Self {
+        let tags = SEVEN_TAG_ROSTER.iter().map(|&(name, value)| (name.to_string(), value.to_string())).collect();
+        GameRecord { board: start.clone(), start, moves: Vec::new(), tags }
+    }
+
+    /// A record of `moves` played from `start`. Errors at the first move
+    /// that is illegal, or comes after the game has ended.
+    pub fn from_moves(start: Board, moves: &[Move]) -> Result<Self, ChessError> {
+        let mut record = GameRecord::new(start);
+        for m in moves {
+            record.push(m)?;
+        }
+        Ok(record)
+    }
+
+    /// Play `m` and add it to the record. The board and record are unchanged
+    /// if it is illegal or the game is over.
+    pub fn push(&mut self, m: &Move) -> Result<(), ChessError> {
+        if self.board.game_over {
+            return Err(ChessError::GameOver);
+        }
+        let m = self.board.find_legal_move(m.from, m.to, m.promotion)?;
+        self.board.apply_move(&m);
+        self.moves.push(m);
+        Ok(())
+    }
+
+    /// Read the first game in `pgn`: its tags, then its moves in SAN up to
+    /// the result token, replayed from the start position (or the `FEN`
+    /// tag's). Comments, NAGs and variations are skipped. Errors name the
+    /// first move that does not parse or is not legal where it is played.
+    ///
+    /// The result is taken from the replayed game, so a resignation reads
+    /// back as "*".
+    pub fn from_pgn(pgn: &str) -> Result<GameRecord, ChessError> {
+        let tokens = tokenize(pgn)?;
+        let tag_count = tokens.iter().take_while(|t| matches!(t, Token::Tag(..))).count();
+        let tags: Vec<(&str, &str)> = tokens[..tag_count]
+            .iter()
+            .filter_map(|t| match t {
+                Token::Tag(name, value) => Some((name.as_str(), value.as_str())),
+                Token::Symbol(_) => None,
+            })
+            .collect();
+        let tag = |name: &str| tags.iter().find(|(n, _)| *n == name).map(|(_, v)| *v);
+
+        let mut start = match tag("FEN") {
+            Some(fen) => Board::from_fen(fen)?,
+            None => Board::new(),
+        };
+        if tag("Variant").is_some_and(|v| v.eq_ignore_ascii_case("King of the Hill") || v.eq_ignore_ascii_case("KingOfTheHill")) {
+            start.variant = Variant::KingOfTheHill;
+        }
+        let mut record = GameRecord::new(start);
+        for &(name, value) in &tags {
+            record.set_tag(name, value);
+        }
+
+        for token in &tokens[tag_count..] {
+            let symbol = match token {
+                Token::Symbol(symbol) => symbol.as_str(),
+                Token::Tag(..) => return Err(invalid("tag pair inside the movetext")),
+            };
+            if RESULT_TOKENS.contains(&symbol) {
+                break;
+            }
+            // Move numbers, alone ("12.", "12...") or run into the move ("12.e4").
+            let san = match symbol.split_once('.') {
+                Some((number, rest)) if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+                    rest.trim_start_matches('.')
+                }
+                _ => symbol,
+            };
+            if san.is_empty() {
+                continue;
+            }
+            let board = &record.board;
+            let (move_number, color) = (board.fullmove_number, board.current_turn);
+            board
+                .parse_san(san)
+                .and_then(|m| record.push(&m))
+                .map_err(|e| ChessError::PgnMove { move_number, color, san: san.to_string(), reason: e.to_string() })?;
+        }
+        Ok(record)
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// The position after the last recorded move.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The position after the first `ply` moves, for stepping through the
+    /// game: `board_at(0)` is the start. `None` past the last move.
+    pub fn board_at(&self, ply: usize) -> Option<Board> {
+        let moves = self.moves.get(..ply)?;
+        let mut board = self.start.clone();
+        board.apply_moves(moves);
+        Some(board)
+    }
+
+    /// Value of the tag `name`, e.g. "White".
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Set a tag, replacing any earlier value. New tags are exported after
+    /// the seven-tag roster in the order they were first set. `Result`,
+    /// `SetUp` and `FEN` are derived from the game and cannot be set.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        if matches!(name, "Result" | "SetUp" | "FEN") {
+            return;
+        }
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// PGN result token: "1-0", "0-1", "1/2-1/2", or "*" while the game is
+    /// still in progress.
+    pub fn result_token(&self) -> &'static str {
+        match self.board.result.as_deref() {
+            None => "*",
+            Some(_) => match self.board.winner() {
+                Some(Color::White) => "1-0",
+                Some(Color::Black) => "0-1",
+                None => "1/2-1/2",
+            },
+        }
+    }
+
+    /// The game in PGN: the seven-tag roster and any other tags, then the
+    /// movetext in SAN with move numbers, ending with the result. Games that
+    /// do not start from the standard position carry `SetUp` and `FEN`
+    /// tags; King of the Hill games a `Variant` tag.
+    pub fn to_pgn(&self) -> String {
+        let result = self.result_token();
+        let mut tags: Vec<(&str, &str)> = Vec::new();
+        for (i, (name, value)) in self.tags.iter().enumerate() {
+            tags.push((name.as_str(), value.as_str()));
+            if i + 1 == SEVEN_TAG_ROSTER.len() {
+                tags.push(("Result", result));
+            }
+        }
+        let start_fen = self.start.to_fen();
+        if start_fen != Board::new().to_fen() {
+            tags.push(("SetUp", "1"));
+            tags.push(("FEN", &start_fen));
+        }
+        if self.start.variant == Variant::KingOfTheHill && self.tag("Variant").is_none() {
+            tags.push(("Variant", "King of the Hill"));
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push('\n');
+
+        let mut line = String::new();
+        for token in self.movetext_tokens().into_iter().chain(std::iter::once(result.to_string())) {
+            if !line.is_empty() && line.len() + 1 + token.len() > PGN_LINE_WIDTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Move numbers and SAN moves, replayed from the start so each move is
+    /// disambiguated against its own position: "1.", "e4", "e5", "2.", ...
+    /// A game starting with Black to move opens with "1...".
+    fn movetext_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut board = self.start.clone();
+        for (i, m) in self.moves.iter().enumerate() {
+            if board.current_turn == Color::White {
+                tokens.push(format!("{}.", board.fullmove_number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", board.fullmove_number));
+            }
+            tokens.push(m.to_san(&board));
+            board.apply_move(m);
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(start: Board, uci: &[&str]) -> GameRecord {
+        let moves: Vec<Move> = uci.iter().map(|m| Move::from_uci(m).unwrap()).collect();
+        GameRecord::from_moves(start, &moves).unwrap()
+    }
+
+    #[test]
+    fn exports_a_short_game() {
+        let mut game = record(Board::new(), &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"]);
+        game.set_tag("White", "Scholar");
+        game.set_tag("Annotator", "Nobody");
+        let pgn = game.to_pgn();
+        assert_eq!(
+            pgn,
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"Scholar\"]\n[Black \"?\"]\n\
+             [Result \"1-0\"]\n[Annotator \"Nobody\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n"
+        );
+    }
+
+    #[test]
+    fn movetext_disambiguates_and_numbers_from_the_start_position() {
+        let start = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R b - - 3 20").unwrap();
+        let game = record(start, &["e8d7", "a1d1", "d7e7", "h1h7", "e7e6", "d1d6"]);
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Result \"*\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4K3/R6R b - - 3 20\"]\n"));
+        assert!(pgn.ends_with("\n20... Kd7 21. Rad1+ Ke7 22. Rh7+ Ke6 23. Rd6+ *\n"), "{pgn}");
+    }
+
+    #[test]
+    fn records_reject_illegal_moves_and_moves_after_the_end() {
+        let mut game = GameRecord::default();
+        assert!(game.push(&Move::from_uci("e2e5").unwrap()).is_err());
+        assert!(game.moves().is_empty());
+        // Knights out and back until threefold repetition ends the game.
+        for _ in 0..2 {
+            for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                game.push(&Move::from_uci(uci).unwrap()).unwrap();
+            }
+        }
+        assert_eq!(game.result_token(), "1/2-1/2");
+        assert_eq!(game.push(&Move::from_uci("e2e4").unwrap()), Err(ChessError::GameOver));
+        assert!(game.to_pgn().ends_with("\n\n1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 1/2-1/2\n"));
+    }
+
+    #[test]
+    fn imports_a_game_skipping_comments_nags_and_variations() {
+        let pgn = r#"[Event "Casual game"]
+[White "Anderssen"]
+[Black "Kieseritzky \"the Immortal\""]
+[Result "1-0"]
+
+% An escaped line.
+1. e4 {the king's pawn} e5 2. f4 $1 exf4 (2... d5 {the counter-gambit} 3. exd5 (3. Nf3)) 3.Bc4 Qh4+ ; check
+4. Kf1 b5?! 5. Bxb5 1-0 6. Nf3
+"#;
+        let game = GameRecord::from_pgn(pgn).unwrap();
+        let uci: Vec<String> = game.moves().iter().map(Move::to_uci).collect();
+        assert_eq!(uci, ["e2e4", "e7e5", "f2f4", "e5f4", "f1c4", "d8h4", "e1f1", "b7b5", "c4b5"]);
+        assert_eq!(game.tag("Event"), Some("Casual game"));
+        assert_eq!(game.tag("Black"), Some("Kieseritzky \"the Immortal\""));
+        assert_eq!(game.tag("Site"), Some("?"));
+        assert_eq!(game.board().to_fen(), "rnb1kbnr/p1pp1ppp/8/1B6/4Pp1q/8/PPPP2PP/RNBQ1KNR b kq - 0 5");
+
+        // Step through: the start, then after 2. f4.
+        assert_eq!(game.board_at(0).unwrap().to_fen(), Board::new().to_fen());
+        assert_eq!(game.board_at(3).unwrap().to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4PP2/8/PPPP2PP/RNBQKBNR b KQkq f3 0 2");
+        assert_eq!(game.board_at(9).unwrap().to_fen(), game.board().to_fen());
+        assert!(game.board_at(10).is_none());
+    }
+
+    #[test]
+    fn imported_game_continues_as_if_played_live() {
+        let game = GameRecord::from_pgn("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 *").unwrap();
+        let live = Board::from_uci_moves(&["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6"]).unwrap();
+        assert_eq!(game.board().to_fen(), "rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 6 4");
+        assert_eq!(game.board().position_history, live.position_history);
+
+        // The imported history counts towards a repetition: one more
+        // shuffle brings the start position round a third time.
+        let mut board = game.board().clone();
+        for uci in ["f3g1", "f6g8"] {
+            board.apply_move(&Move::from_uci(uci).unwrap());
+        }
+        assert!(board.game_over);
+    }
+
+    #[test]
+    fn import_names_the_illegal_move() {
+        let err = GameRecord::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 Nxe4 4. O-O *").unwrap_err();
+        assert_eq!(err, ChessError::PgnMove { move_number: 3, color: Color::Black, san: "Nxe4".to_string(), reason: "Illegal move 'Nxe4'".to_string() });
+        assert_eq!(err.to_string(), "Move 3... Nxe4: Illegal move 'Nxe4'");
+        assert!(matches!(GameRecord::from_pgn("1. e4 {unfinished"), Err(ChessError::InvalidPgn { .. })));
+        assert!(matches!(GameRecord::from_pgn("[Event \"x\"\n1. e4"), Err(ChessError::InvalidPgn { .. })));
+        assert!(matches!(GameRecord::from_pgn("1. Qxf7#"), Err(ChessError::PgnMove { move_number: 1, color: Color::White, .. })));
+    }
+
+    #[test]
+    fn exported_games_import_unchanged() {
+        let start = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R b - - 3 20").unwrap();
+        let mut game = record(start, &["e8d7", "a1d1", "d7e7", "h1h7", "e7e6", "d1d6"]);
+        game.set_tag("Round", "3");
+        let pgn = game.to_pgn();
+        let imported = GameRecord::from_pgn(&pgn).unwrap();
+        assert_eq!(imported.to_pgn(), pgn);
+        assert_eq!(imported.board().to_fen(), game.board().to_fen());
+    }
+
+    #[test]
+    fn long_movetext_wraps_at_eighty_columns() {
+        let mut board = Board::new();
+        let mut game = GameRecord::default();
+        for san in "e4 e5 Nf3 d6 d4 Bg4 dxe5 Bxf3 Qxf3 dxe5 Bc4 Nf6 Qb3 Qe7 Nc3 c6 Bg5 b5 Nxb5 cxb5 Bxb5+ Nbd7 O-O-O Rd8 Rxd7 Rxd7 Rd1 Qe6 Bxd7+ Nxd7 Qb8+ Nxb8 Rd8#".split(' ') {
+            let m = board.parse_san(san).unwrap();
+            board.apply_move(&m);
+            game.push(&m).unwrap();
+        }
+        let pgn = game.to_pgn();
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|l| l.len() <= PGN_LINE_WIDTH));
+        assert!(movetext.join(" ").starts_with("1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3"));
+        assert!(movetext.join(" ").ends_with("16. Qb8+ Nxb8 17. Rd8# 1-0"));
+    }
+}
@@ -10,6 +10,69 @@ pub enum PieceType {
     Pawn,
 }
 
+impl PieceType {
+    /// Every piece type, in `index()` order.
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    /// Stable 0..6 index, for per-type tables.
+    pub fn index(self) -> usize {
+        match self {
+            PieceType::Pawn => 0,
+            PieceType::Knight => 1,
+            PieceType::Bishop => 2,
+            PieceType::Rook => 3,
+            PieceType::Queen => 4,
+            PieceType::King => 5,
+        }
+    }
+
+    /// Standard material value in pawns (P=1, N=B=3, R=5, Q=9). The king is
+    /// 0: losing it is checkmate, not a material loss.
+    pub fn value(self) -> f64 {
+        match self {
+            PieceType::Pawn => 1.0,
+            PieceType::Knight => 3.0,
+            PieceType::Bishop => 3.0,
+            PieceType::Rook => 5.0,
+            PieceType::Queen => 9.0,
+            PieceType::King => 0.0,
+        }
+    }
+
+    /// Upper-case letter used by FEN and SAN: P, N, B, R, Q, K.
+    pub fn letter(self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Rook => 'R',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+
+    /// Parse a promotion choice: a piece name ("Queen", "queen") or its
+    /// letter ("q", "N"). Kings and pawns are named in the error.
+    pub fn parse_promotion(s: &str) -> Result<PieceType, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "q" | "queen" => Ok(PieceType::Queen),
+            "r" | "rook" => Ok(PieceType::Rook),
+            "b" | "bishop" => Ok(PieceType::Bishop),
+            "n" | "knight" => Ok(PieceType::Knight),
+            "k" | "king" => Err("Cannot promote to a king".to_string()),
+            "p" | "pawn" => Err("Cannot promote to a pawn".to_string()),
+            _ => Err(format!("Unknown promotion piece '{s}'")),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub enum Color {
     White,
@@ -35,4 +98,50 @@ impl Piece {
     pub fn new(piece_type: PieceType, color: Color) -> Self {
         Piece { piece_type, color }
     }
+
+    /// The piece for a FEN letter ('K', 'n', ...), if it is one.
+    pub fn from_fen_char(c: char) -> Option<Piece> {
+        let piece_type = match c.to_ascii_uppercase() {
+            'P' => PieceType::Pawn,
+            'N' => PieceType::Knight,
+            'B' => PieceType::Bishop,
+            'R' => PieceType::Rook,
+            'Q' => PieceType::Queen,
+            'K' => PieceType::King,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        Some(Piece::new(piece_type, color))
+    }
+
+    /// FEN letter: upper case for White, lower case for Black.
+    pub fn fen_char(self) -> char {
+        match self.color {
+            Color::White => self.piece_type.letter(),
+            Color::Black => self.piece_type.letter().to_ascii_lowercase(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_promotion_names_and_letters() {
+        for (input, expected) in [
+            ("Queen", PieceType::Queen),
+            ("queen", PieceType::Queen),
+            ("q", PieceType::Queen),
+            ("ROOK", PieceType::Rook),
+            ("b", PieceType::Bishop),
+            ("N", PieceType::Knight),
+            ("knight", PieceType::Knight),
+        ] {
+            assert_eq!(PieceType::parse_promotion(input), Ok(expected), "{input}");
+        }
+        assert_eq!(PieceType::parse_promotion("King"), Err("Cannot promote to a king".to_string()));
+        assert_eq!(PieceType::parse_promotion("p"), Err("Cannot promote to a pawn".to_string()));
+        assert!(PieceType::parse_promotion("dragon").is_err());
+    }
 }
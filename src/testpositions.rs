@@ -0,0 +1,88 @@
+// Named positions for tests, benchmarks and sanity checks, as FEN. Each
+// constant says what it is for; `ALL` lists them with their names.
+
+/// The standard start position.
+pub const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// A sharp middlegame: the Yugoslav Attack against the Sicilian Dragon,
+/// with the kings castled on opposite wings.
+pub const SHARP_MIDDLEGAME: &str = "r2q1rk1/pp1bppbp/2np1np1/8/2BNP3/2N1BP2/PPPQ2PP/2KR3R b - - 6 10";
+
+/// A king and pawn endgame with locked queenside pawns.
+pub const PAWN_ENDGAME: &str = "8/8/1p3k2/p1p5/P1P5/1P3K2/8/8 w - - 0 1";
+
+/// White mates in two on the back rank: 1.Re8+ Rxe8 2.Rxe8#.
+pub const MATE_IN_TWO: &str = "3r2k1/ppp2ppp/8/8/8/8/4RPPP/4R1K1 w - - 0 1";
+
+/// Black to move is stalemated.
+pub const STALEMATE: &str = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1";
+
+/// Playing `THREEFOLD_MOVES` from here repeats this position a third time.
+pub const THREEFOLD_SETUP: &str = START;
+
+/// Knight shuffles that bring `THREEFOLD_SETUP` back twice.
+pub const THREEFOLD_MOVES: &[&str] = &["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"];
+
+/// The "Kiwipete" perft reference position, full of castling, en passant
+/// and promotion edge cases. Perft 1–3: 48, 2039, 97862.
+pub const PERFT_REFERENCE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// Deliberately illegal: the side not to move (Black) is in check. For
+/// exercising `Board::validate`.
+pub const ILLEGAL_OPPONENT_IN_CHECK: &str = "4k3/8/8/8/8/8/8/4R1K1 w - - 0 1";
+
+/// Every position above, by name.
+pub const ALL: &[(&str, &str)] = &[
+    ("start", START),
+    ("sharp_middlegame", SHARP_MIDDLEGAME),
+    ("pawn_endgame", PAWN_ENDGAME),
+    ("mate_in_two", MATE_IN_TWO),
+    ("stalemate", STALEMATE),
+    ("threefold_setup", THREEFOLD_SETUP),
+    ("perft_reference", PERFT_REFERENCE),
+    ("illegal_opponent_in_check", ILLEGAL_OPPONENT_IN_CHECK),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, PositionWarning, TerminalReason};
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap_or_else(|e| panic!("{fen}: {e}"))
+    }
+
+    #[test]
+    fn every_position_parses_and_only_the_illegal_ones_warn() {
+        for (name, fen) in ALL {
+            let warnings = board(fen).validate();
+            if name.starts_with("illegal_") {
+                assert!(!warnings.is_empty(), "{name} should be illegal");
+            } else {
+                assert!(warnings.is_empty(), "{name}: {warnings:?}");
+            }
+        }
+        assert_eq!(board(ILLEGAL_OPPONENT_IN_CHECK).validate(), vec![PositionWarning::OpponentInCheck]);
+    }
+
+    #[test]
+    fn positions_are_what_they_say() {
+        assert_eq!(board(START).position_hash(), Board::new().position_hash());
+        let mate = board(MATE_IN_TWO);
+        assert!(mate.find_mate(1).is_none());
+        assert_eq!(mate.find_mate(2).unwrap().to_uci(), "e2e8");
+        assert_eq!(board(STALEMATE).terminal_reason(), Some(TerminalReason::Stalemate));
+        assert!(board(PAWN_ENDGAME).is_endgame());
+        assert!(!board(SHARP_MIDDLEGAME).is_endgame());
+
+        let mut repeated = board(THREEFOLD_SETUP);
+        for uci in THREEFOLD_MOVES {
+            repeated.apply_uci(uci).unwrap();
+        }
+        assert_eq!(repeated.terminal_reason(), Some(TerminalReason::Repetition));
+
+        let kiwipete = board(PERFT_REFERENCE);
+        assert_eq!(kiwipete.perft(1), 48);
+        assert_eq!(kiwipete.perft(2), 2039);
+    }
+}
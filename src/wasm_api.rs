@@ -1,5 +1,9 @@
-use crate::board::Board;
-use crate::engine::{pick_move, evaluate_breakdown, AiConfig};
+use crate::board::{Board, PositionWarning, TerminalReason, Variant};
+use crate::error::ChessError;
+use crate::engine::{analyze_game, captured_material, describe_choice, display_eval, evaluate_all_moves, pick_move, pick_move_or_reason, pick_move_restricted, AiConfig, BreakdownCache, PickResult, RootMoveStats};
+use crate::moves::{index_to_square, Move};
+use crate::opening::opening_name;
+use crate::pgn::GameRecord;
 use crate::piece::PieceType;
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
@@ -25,9 +29,23 @@ struct BoardState {
     result: Option<String>,
     is_in_check: bool,
     legal_moves: Vec<MoveJson>,
+    legal_move_count: usize,
     captured_white: Vec<String>,
     captured_black: Vec<String>,
+    /// Point totals of `captured_white` / `captured_black` (P=1 … Q=9).
+    captured_white_value: f64,
+    captured_black_value: f64,
     last_move: Option<[[usize; 2]; 2]>,
+    terminal_reason: Option<String>,
+    moves_until_fifty_move_draw: u32,
+    /// Castling rights left, as in FEN: e.g. "KQkq", "Kq" or "-".
+    castling_rights: String,
+    /// Times the current position has occurred (3 draws by repetition).
+    repetition_count: usize,
+    /// Non-fatal problems with the position (e.g. a set-up with two kings).
+    warnings: Vec<String>,
+    /// The side not to move would mate in one if it were their turn.
+    opponent_has_mate_in_one: bool,
 }
 
 #[derive(Serialize)]
@@ -37,6 +55,47 @@ struct MoveResult {
     error: Option<String>,
 }
 
+#[derive(Serialize)]
+struct AnalysisPointJson {
+    ply: usize,
+    best: String,
+    best_score: f64,
+    played: String,
+    played_score: f64,
+    blunder: bool,
+}
+
+#[derive(Serialize)]
+struct DisplayEvalJson {
+    pawns: f64,
+    mate_in: Option<i32>,
+    text: String,
+}
+
+#[derive(Serialize)]
+struct AiMoveJson {
+    uci: String,
+    from: [usize; 2],
+    to: [usize; 2],
+    promotion: Option<String>,
+    score: f64,
+    evals: u64,
+}
+
+#[derive(Serialize)]
+struct RootStatJson {
+    uci: String,
+    nodes: u64,
+    score: f64,
+}
+
+#[derive(Serialize)]
+struct MoveEvalJson {
+    uci: String,
+    /// Search score from the mover's perspective.
+    score: f64,
+}
+
 #[derive(Serialize)]
 struct SquareMoveJson {
     to: [usize; 2],
@@ -49,6 +108,13 @@ struct EvalBreakdownJson {
     material: f64,
     centre: f64,
     passed_pawns: f64,
+    outposts: f64,
+    rooks: f64,
+    king_attack: f64,
+    king_safety: f64,
+    space: f64,
+    development: f64,
+    king_of_the_hill: f64,
     draw_penalty: f64,
     total: f64,
 }
@@ -71,13 +137,24 @@ fn color_to_string(c: crate::piece::Color) -> String {
     }
 }
 
-fn string_to_piece_type(s: &str) -> Option<PieceType> {
-    match s {
-        "Queen" => Some(PieceType::Queen),
-        "Rook" => Some(PieceType::Rook),
-        "Bishop" => Some(PieceType::Bishop),
-        "Knight" => Some(PieceType::Knight),
-        _ => None,
+fn terminal_reason_to_string(reason: TerminalReason) -> String {
+    match reason {
+        TerminalReason::Checkmate => "Checkmate".to_string(),
+        TerminalReason::KingOfTheHill => "KingOfTheHill".to_string(),
+        TerminalReason::Stalemate => "Stalemate".to_string(),
+        TerminalReason::FiftyMoveRule => "FiftyMoveRule".to_string(),
+        TerminalReason::Repetition => "Repetition".to_string(),
+        TerminalReason::InsufficientMaterial => "InsufficientMaterial".to_string(),
+    }
+}
+
+fn position_warning_to_string(warning: PositionWarning) -> String {
+    match warning {
+        PositionWarning::OpponentInCheck => "The side not to move is in check".to_string(),
+        PositionWarning::PawnOnBackRank((row, col)) => format!("Pawn on back rank at {}", index_to_square((row, col))),
+        PositionWarning::TooManyPawns(c) => format!("{} has more than 8 pawns", color_to_string(c)),
+        PositionWarning::MissingKing(c) => format!("{} has no king", color_to_string(c)),
+        PositionWarning::DuplicateKings(c) => format!("{} has more than one king", color_to_string(c)),
     }
 }
 
@@ -111,10 +188,19 @@ fn build_board_state(board: &Board) -> BoardState {
         game_over: board.game_over,
         result: board.result.clone(),
         is_in_check: board.is_in_check(board.current_turn),
+        legal_move_count: legal_moves.len(),
         legal_moves,
         captured_white: board.captured_white.iter().map(|pt| piece_type_to_string(*pt)).collect(),
         captured_black: board.captured_black.iter().map(|pt| piece_type_to_string(*pt)).collect(),
+        captured_white_value: captured_material(board, crate::piece::Color::White),
+        captured_black_value: captured_material(board, crate::piece::Color::Black),
         last_move: board.last_move.map(|((fr, fc), (tr, tc))| [[fr, fc], [tr, tc]]),
+        terminal_reason: board.terminal_reason().map(terminal_reason_to_string),
+        moves_until_fifty_move_draw: board.moves_until_fifty_move_draw(),
+        castling_rights: board.castling_rights.to_fen(),
+        repetition_count: board.repetition_count(),
+        warnings: board.validate().into_iter().map(position_warning_to_string).collect(),
+        opponent_has_mate_in_one: board.opponent_has_mate_in_one(),
     }
 }
 
@@ -123,6 +209,17 @@ pub struct Game {
     board: Board,
     ai_config: AiConfig,
     last_evals: u64,
+    last_root_stats: Vec<RootMoveStats>,
+    /// Every move played since the start, for `analyze_history`.
+    move_history: Vec<Move>,
+    /// White-perspective search score of the AI's last move, cleared once the
+    /// human moves.
+    last_score: Option<f64>,
+    /// Ply of `move_history` being reviewed, or `None` for the live position.
+    view_ply: Option<usize>,
+    /// Eval breakdown of the live position, cleared whenever the board or
+    /// the config changes.
+    breakdown_cache: BreakdownCache,
 }
 
 #[wasm_bindgen]
@@ -130,6 +227,21 @@ pub fn build_timestamp() -> String {
     env!("BUILD_TIMESTAMP").to_string()
 }
 
+impl Game {
+    /// Record a search result and play its move.
+    fn play_ai_result(&mut self, result: PickResult) {
+        self.last_evals = result.evals;
+        self.last_root_stats = result.root_stats;
+        self.last_score = Some(match self.board.current_turn {
+            crate::piece::Color::White => result.score,
+            crate::piece::Color::Black => -result.score,
+        });
+        self.board.apply_move(&result.mv);
+        self.move_history.push(result.mv);
+        self.breakdown_cache.clear();
+    }
+}
+
 #[wasm_bindgen]
 impl Game {
     #[wasm_bindgen(constructor)]
@@ -138,22 +250,45 @@ impl Game {
             board: Board::new(),
             ai_config: AiConfig::new(),
             last_evals: 0,
+            last_root_stats: Vec::new(),
+            move_history: Vec::new(),
+            last_score: None,
+            view_ply: None,
+            breakdown_cache: BreakdownCache::default(),
         }
     }
 
     pub fn set_module(&mut self, name: &str, enabled: bool) {
+        self.breakdown_cache.clear();
         match name {
             "mate" => self.ai_config.mate_module = enabled,
             "material" => self.ai_config.material_module = enabled,
             "centre" => self.ai_config.centre_module = enabled,
             "passed_pawns" => self.ai_config.passed_pawn_module = enabled,
+            "outposts" => self.ai_config.outpost_module = enabled,
+            "rooks" => self.ai_config.rook_module = enabled,
             "draw_penalty" => self.ai_config.draw_penalty_module = enabled,
+            "king_attack" => self.ai_config.attack_module = enabled,
+            "king_safety" => self.ai_config.king_safety_module = enabled,
+            "space" => self.ai_config.space_module = enabled,
+            "development" => self.ai_config.development_module = enabled,
+            _ => {}
+        }
+    }
+
+    /// Switch the ruleset: "Standard" or "KingOfTheHill".
+    pub fn set_variant(&mut self, name: &str) {
+        self.breakdown_cache.clear();
+        match name {
+            "Standard" => self.board.variant = Variant::Standard,
+            "KingOfTheHill" => self.board.variant = Variant::KingOfTheHill,
             _ => {}
         }
     }
 
+    /// Depth 0 is the "instant" static greedy mode.
     pub fn set_depth(&mut self, depth: u32) {
-        self.ai_config.depth = depth.clamp(1, 3);
+        self.ai_config.depth = depth.min(3);
     }
 
     pub fn set_auto_deepen(&mut self, enabled: bool, min_evals: u64) {
@@ -161,6 +296,29 @@ impl Game {
         self.ai_config.min_evals = min_evals;
     }
 
+    /// Seed the AI's tie-break so repeated searches of a position agree.
+    /// Pass `undefined` to go back to random tie-breaks.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.ai_config.seed = seed;
+    }
+
+    /// Cap each AI search (moves and hints) at `ms` milliseconds so slow
+    /// devices stay responsive; 0 removes the limit (the default).
+    pub fn set_time_budget_ms(&mut self, ms: u32) {
+        self.ai_config.time_budget_ms = (ms > 0).then_some(ms as u64);
+    }
+
+    /// Teaching mode: the AI may not move its queen before fullmove
+    /// `move_number` unless it has no other move; 0 lifts the rule.
+    pub fn set_no_early_queen_until(&mut self, move_number: u32) {
+        self.ai_config.no_early_queen_until = move_number;
+    }
+
+    /// Always pick the same move among equally scored ones (no RNG).
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.ai_config.deterministic = enabled;
+    }
+
     pub fn get_board_state(&self) -> JsValue {
         let state = build_board_state(&self.board);
         serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL)
@@ -182,24 +340,25 @@ impl Game {
             return serde_wasm_bindgen::to_value(&err).unwrap_or(JsValue::NULL);
         }
 
-        let promo_pt = promotion.as_deref().and_then(string_to_piece_type);
-
-        let legal_moves = self.board.generate_legal_moves(self.board.current_turn);
-        let matching_move = legal_moves.iter().find(|m| {
-            m.from == (from_row, from_col) && m.to == (to_row, to_col) && m.promotion == promo_pt
-        });
+        let matching_move = promotion
+            .as_deref()
+            .map(|piece| PieceType::parse_promotion(piece).map_err(|reason| ChessError::InvalidPromotion { reason }))
+            .transpose()
+            .and_then(|promo_pt| self.board.find_legal_move((from_row, from_col), (to_row, to_col), promo_pt));
 
         match matching_move {
-            Some(m) => {
-                let m = m.clone();
+            Ok(m) => {
                 self.board.apply_move(&m);
+                self.move_history.push(m);
+                self.last_score = None;
+                self.breakdown_cache.clear();
                 let state = build_board_state(&self.board);
                 serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL)
             }
-            None => {
+            Err(reason) => {
                 let err = MoveResult {
                     board_state: None,
-                    error: Some("Illegal move".to_string()),
+                    error: Some(reason.to_string()),
                 };
                 serde_wasm_bindgen::to_value(&err).unwrap_or(JsValue::NULL)
             }
@@ -212,17 +371,46 @@ impl Game {
             return serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL);
         }
 
-        match pick_move(&self.board, &self.ai_config) {
+        // On Err the board state's terminal_reason reports why no move was made.
+        if let Ok(result) = pick_move_or_reason(&self.board, &self.ai_config) {
+            self.play_ai_result(result);
+        }
+        let state = build_board_state(&self.board);
+        serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL)
+    }
+
+    /// Like `make_ai_move`, but the AI may only choose among `uci_moves`
+    /// (e.g. ["g1f3", "b1c3"]). Returns a `MoveResult` with an error if none
+    /// of them is legal.
+    pub fn make_ai_move_restricted(&mut self, uci_moves: Vec<String>) -> JsValue {
+        let allowed: Vec<Move> = uci_moves.iter().filter_map(|uci| Move::from_uci(uci)).collect();
+        let result = match pick_move_restricted(&self.board, &self.ai_config, &allowed) {
             Some(result) => {
-                self.last_evals = result.evals;
-                self.board.apply_move(&result.mv);
-                let state = build_board_state(&self.board);
-                serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL)
+                self.play_ai_result(result);
+                MoveResult { board_state: Some(build_board_state(&self.board)), error: None }
             }
-            None => {
-                let state = build_board_state(&self.board);
-                serde_wasm_bindgen::to_value(&state).unwrap_or(JsValue::NULL)
+            None => MoveResult { board_state: None, error: Some("No legal move among the allowed moves".to_string()) },
+        };
+        serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
+    }
+
+    /// Compute the AI's move without applying it, so the UI can preview or
+    /// animate it first and then commit it with `make_move`. With a seed set,
+    /// the result matches what `make_ai_move` would play.
+    pub fn compute_ai_move(&self) -> JsValue {
+        match pick_move(&self.board, &self.ai_config) {
+            Some(result) => {
+                let json = AiMoveJson {
+                    uci: result.mv.to_uci(),
+                    from: [result.mv.from.0, result.mv.from.1],
+                    to: [result.mv.to.0, result.mv.to.1],
+                    promotion: result.mv.promotion.map(piece_type_to_string),
+                    score: result.score,
+                    evals: result.evals,
+                };
+                serde_wasm_bindgen::to_value(&json).unwrap_or(JsValue::NULL)
             }
+            None => JsValue::NULL,
         }
     }
 
@@ -242,6 +430,19 @@ impl Game {
         }
     }
 
+    /// The move the AI would play here, explained in a sentence for
+    /// learners, e.g. "Develops a knight toward the centre."
+    pub fn explain_ai_move(&self) -> String {
+        describe_choice(&self.board, &self.ai_config)
+    }
+
+    /// True if `uci` (e.g. "e2e4", "e7e8q") is a legal move in the live
+    /// position, for callers that generate moves themselves. Malformed input
+    /// is simply not legal.
+    pub fn is_legal_uci(&self, uci: &str) -> bool {
+        Move::from_uci(uci).is_some_and(|m| self.board.is_legal(&m))
+    }
+
     pub fn get_legal_moves_for_square(&self, row: usize, col: usize) -> JsValue {
         let legal_moves = self.board.generate_legal_moves(self.board.current_turn);
         let square_moves: Vec<SquareMoveJson> = legal_moves
@@ -256,17 +457,210 @@ impl Game {
         serde_wasm_bindgen::to_value(&square_moves).unwrap_or(JsValue::NULL)
     }
 
+    /// All legal checks, captures and promotions for the side to move, for
+    /// the "find the tactic" highlight.
+    pub fn get_forcing_moves(&self) -> JsValue {
+        let moves: Vec<MoveJson> = self
+            .board
+            .forcing_moves(self.board.current_turn)
+            .iter()
+            .map(|m| MoveJson {
+                from: [m.from.0, m.from.1],
+                to: [m.to.0, m.to.1],
+                promotion: m.promotion.map(piece_type_to_string),
+            })
+            .collect();
+        serde_wasm_bindgen::to_value(&moves).unwrap_or(JsValue::NULL)
+    }
+
+    /// Squares of `color`'s ("White"/"Black") pieces that the opponent can
+    /// win by capturing, for the "you're hanging a piece" warning.
+    pub fn get_hanging_pieces(&self, color: &str) -> JsValue {
+        let color = match color {
+            "White" => crate::piece::Color::White,
+            "Black" => crate::piece::Color::Black,
+            _ => return JsValue::NULL,
+        };
+        let squares: Vec<[usize; 2]> = self.board.hanging_pieces(color).into_iter().map(|(r, c)| [r, c]).collect();
+        serde_wasm_bindgen::to_value(&squares).unwrap_or(JsValue::NULL)
+    }
+
+    /// 8x8 grid (rows = ranks) counting how many of the side to move's
+    /// pieces can move to each square, for the "show all my moves" heatmap.
+    pub fn get_move_targets_heatmap(&self) -> JsValue {
+        let grid = self.board.legal_move_targets(self.board.current_turn);
+        serde_wasm_bindgen::to_value(&grid).unwrap_or(JsValue::NULL)
+    }
+
     pub fn get_last_evals(&self) -> u64 {
         self.last_evals
     }
 
-    pub fn get_eval_breakdown(&self) -> JsValue {
-        let breakdown = evaluate_breakdown(&self.board, crate::piece::Color::White, &self.ai_config);
+    /// Nodes and score per root move from the last AI move, in search order.
+    pub fn get_root_stats(&self) -> JsValue {
+        let stats: Vec<RootStatJson> = self
+            .last_root_stats
+            .iter()
+            .map(|s| RootStatJson { uci: s.mv.to_uci(), nodes: s.nodes, score: s.score })
+            .collect();
+        serde_wasm_bindgen::to_value(&stats).unwrap_or(JsValue::NULL)
+    }
+
+    /// Search score of every legal move in the live position, best first,
+    /// for the move heatmap. Runs a full search, so call it sparingly.
+    pub fn get_move_evaluations(&self) -> JsValue {
+        let evals: Vec<MoveEvalJson> = evaluate_all_moves(&self.board, &self.ai_config)
+            .into_iter()
+            .map(|(mv, score)| MoveEvalJson { uci: mv.to_uci(), score })
+            .collect();
+        serde_wasm_bindgen::to_value(&evals).unwrap_or(JsValue::NULL)
+    }
+
+    /// Show the position after `ply` moves of the game (`undefined` for the
+    /// live position) without changing the game itself.
+    pub fn view_ply(&mut self, ply: Option<usize>) -> JsValue {
+        self.view_ply = ply.map(|p| p.min(self.move_history.len()));
+        let board = match self.view_ply {
+            Some(p) => Board::from_moves(self.board.variant, &self.move_history[..p]),
+            None => Some(self.board.clone()),
+        };
+        match board {
+            Some(board) => serde_wasm_bindgen::to_value(&build_board_state(&board)).unwrap_or(JsValue::NULL),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Branch from the position being viewed: it becomes the live game, and
+    /// the moves after it are dropped. Repetition history is rebuilt from the
+    /// truncated line.
+    pub fn play_from_current(&mut self) -> JsValue {
+        if let Some(ply) = self.view_ply.take() {
+            self.move_history.truncate(ply);
+            if let Some(board) = Board::from_moves(self.board.variant, &self.move_history) {
+                self.board = board;
+            }
+            self.last_score = None;
+            self.last_root_stats.clear();
+            self.breakdown_cache.clear();
+        }
+        self.get_board_state()
+    }
+
+    /// The live position as FEN.
+    pub fn get_fen(&self) -> String {
+        self.board.to_fen()
+    }
+
+    /// FEN of the starting position (index 0) and after every move played,
+    /// for review and debugging.
+    pub fn get_fen_history(&self) -> JsValue {
+        match Board::fen_history(self.board.variant, &self.move_history) {
+            Some(fens) => serde_wasm_bindgen::to_value(&fens).unwrap_or(JsValue::NULL),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// The game so far as PGN, for saving. `None` if the history no longer
+    /// replays from the start position.
+    pub fn get_pgn(&self) -> Option<String> {
+        let mut start = Board::new();
+        start.variant = self.board.variant;
+        GameRecord::from_moves(start, &self.move_history).ok().map(|record| record.to_pgn())
+    }
+
+    /// Replace the game with the one in `pgn`, replayed from the start so the
+    /// history and repetition state are as if it were played here. Returns
+    /// the new board state, or a `MoveResult` error naming the failing move
+    /// with the game unchanged. Games set up from a FEN are not supported.
+    pub fn load_pgn(&mut self, pgn: &str) -> JsValue {
+        let record = GameRecord::from_pgn(pgn).and_then(|record| {
+            let mut start = Board::new();
+            start.variant = record.start().variant;
+            if record.start().to_fen() == start.to_fen() {
+                Ok(record)
+            } else {
+                Err(ChessError::InvalidPgn { reason: "only games from the standard start position can be loaded".to_string() })
+            }
+        });
+        match record {
+            Ok(record) => {
+                self.board = record.board().clone();
+                self.move_history = record.moves().to_vec();
+                self.view_ply = None;
+                self.last_score = None;
+                self.last_root_stats.clear();
+                self.breakdown_cache.clear();
+                self.get_board_state()
+            }
+            Err(reason) => {
+                let err = MoveResult { board_state: None, error: Some(reason.to_string()) };
+                serde_wasm_bindgen::to_value(&err).unwrap_or(JsValue::NULL)
+            }
+        }
+    }
+
+    /// Every legal move for the side to move in SAN, for move lists.
+    pub fn get_legal_moves_san(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.board.legal_moves_san()).unwrap_or(JsValue::NULL)
+    }
+
+    /// Material code such as "KRPvKR" (see `Board::material_signature`),
+    /// for debugging endgame handling.
+    pub fn get_material_signature(&self) -> String {
+        self.board.material_signature()
+    }
+
+    /// Name of the opening played so far, e.g. "Ruy Lopez", if it is in the
+    /// built-in table.
+    pub fn get_opening_name(&self) -> Option<String> {
+        opening_name(&self.move_history).map(str::to_string)
+    }
+
+    /// Review the game so far: best move, best score and played-move score
+    /// for every ply, with blunders flagged. Drives the eval graph.
+    pub fn analyze_history(&self) -> JsValue {
+        let points: Vec<AnalysisPointJson> = analyze_game(self.board.variant, &self.move_history, &self.ai_config)
+            .into_iter()
+            .map(|p| AnalysisPointJson {
+                ply: p.ply,
+                best: p.best.to_uci(),
+                best_score: p.best_score,
+                played: p.played.to_uci(),
+                played_score: p.played_score,
+                blunder: p.blunder,
+            })
+            .collect();
+        serde_wasm_bindgen::to_value(&points).unwrap_or(JsValue::NULL)
+    }
+
+    /// Eval bar value from White's perspective: the AI's last search score
+    /// if the human hasn't moved since, otherwise the static evaluation.
+    /// Clamped to ±10 pawns, with mates shown as "M3" etc.
+    pub fn get_display_eval(&mut self) -> JsValue {
+        let score = match self.last_score {
+            Some(score) => score,
+            None => self.breakdown_cache.get(&self.board, &self.ai_config).total,
+        };
+        let display = display_eval(score);
+        let json = DisplayEvalJson { pawns: display.pawns, mate_in: display.mate_in, text: display.text };
+        serde_wasm_bindgen::to_value(&json).unwrap_or(JsValue::NULL)
+    }
+
+    /// Cached per position (see `BreakdownCache`), so polling is cheap.
+    pub fn get_eval_breakdown(&mut self) -> JsValue {
+        let breakdown = self.breakdown_cache.get(&self.board, &self.ai_config);
         let json = EvalBreakdownJson {
             mate: breakdown.mate,
             material: breakdown.material,
             centre: breakdown.centre,
             passed_pawns: breakdown.passed_pawns,
+            outposts: breakdown.outposts,
+            rooks: breakdown.rooks,
+            king_attack: breakdown.king_attack,
+            king_safety: breakdown.king_safety,
+            space: breakdown.space,
+            development: breakdown.development,
+            king_of_the_hill: breakdown.king_of_the_hill,
             draw_penalty: breakdown.draw_penalty,
             total: breakdown.total,
         };